@@ -0,0 +1,158 @@
+use crate::ui::content::shared::OUTLIER_THRESHOLD;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Fewer buckets than this and a series has no baseline to stand out from.
+const MIN_BUCKETS: usize = 7;
+/// Robust z-score above which a bucket counts as a spike.
+const Z_THRESHOLD: f64 = 3.5;
+/// Turns the median absolute deviation into a standard deviation for
+/// normally distributed noise.
+const MAD_SCALE: f64 = 1.4826;
+
+/// A bucket in which one series spent or used far more than it usually does.
+pub struct Anomaly {
+    pub date: DateTime<Utc>,
+    pub series: String,
+    pub value: f64,
+    /// The series' typical bucket.
+    pub median: f64,
+}
+
+impl Anomaly {
+    /// How many times its usual value the series reached.
+    pub fn ratio(&self) -> f64 {
+        self.value / self.median
+    }
+}
+
+/// Spikes in each series of `values`, by how many median absolute
+/// deviations they sit above the series' median. `buckets` are the buckets
+/// the API reported; a series has zero in those it has no value for.
+///
+/// Series that are zero in most buckets have no typical value and are
+/// skipped. A flat series (zero deviation) still flags buckets past
+/// `OUTLIER_THRESHOLD` times its median, like the chart scale does.
+pub fn detect(
+    values: &HashMap<String, HashMap<DateTime<Utc>, f64>>,
+    buckets: &[DateTime<Utc>],
+) -> Vec<Anomaly> {
+    if buckets.len() < MIN_BUCKETS {
+        return Vec::new();
+    }
+
+    let mut anomalies = Vec::new();
+    for (series, by_date) in values {
+        let series_values: Vec<f64> = buckets
+            .iter()
+            .map(|date| by_date.get(date).copied().unwrap_or(0.0))
+            .collect();
+        let median = median_of(series_values.clone());
+        if median <= 0.0 {
+            continue;
+        }
+        let deviations = series_values.iter().map(|v| (v - median).abs()).collect();
+        let spread = MAD_SCALE * median_of(deviations);
+
+        for (date, value) in buckets.iter().zip(&series_values) {
+            let spike = if spread > 0.0 {
+                (value - median) / spread > Z_THRESHOLD
+            } else {
+                *value > median * OUTLIER_THRESHOLD
+            };
+            if spike {
+                anomalies.push(Anomaly {
+                    date: *date,
+                    series: series.clone(),
+                    value: *value,
+                    median,
+                });
+            }
+        }
+    }
+
+    // Newest first, the biggest spike of a bucket on top.
+    anomalies.sort_by(|a, b| {
+        b.date
+            .cmp(&a.date)
+            .then_with(|| b.ratio().total_cmp(&a.ratio()))
+    });
+    anomalies
+}
+
+fn median_of(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn buckets(len: usize) -> Vec<DateTime<Utc>> {
+        let start = DateTime::from_timestamp(1_709_251_200, 0).unwrap();
+        (0..len).map(|i| start + Duration::days(i as i64)).collect()
+    }
+
+    fn series(
+        buckets: &[DateTime<Utc>],
+        values: &[f64],
+    ) -> HashMap<String, HashMap<DateTime<Utc>, f64>> {
+        let by_date = buckets
+            .iter()
+            .copied()
+            .zip(values.iter().copied())
+            .collect();
+        HashMap::from([("gpt-4o".to_string(), by_date)])
+    }
+
+    #[test]
+    fn flat_series_has_no_anomalies() {
+        let buckets = buckets(14);
+        assert!(detect(&series(&buckets, &[10.0; 14]), &buckets).is_empty());
+    }
+
+    #[test]
+    fn single_spike_is_flagged() {
+        let buckets = buckets(10);
+        let values = [9.0, 11.0, 10.0, 12.0, 8.0, 10.0, 60.0, 11.0, 9.0, 10.0];
+        let anomalies = detect(&series(&buckets, &values), &buckets);
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].date, buckets[6]);
+        assert_eq!(anomalies[0].value, 60.0);
+        assert_eq!(anomalies[0].median, 10.0);
+        assert_eq!(anomalies[0].ratio(), 6.0);
+    }
+
+    #[test]
+    fn spike_on_flat_series_uses_outlier_threshold() {
+        let buckets = buckets(8);
+        let mut values = [10.0; 8];
+        values[3] = 10.0 * OUTLIER_THRESHOLD;
+        assert!(detect(&series(&buckets, &values), &buckets).is_empty());
+
+        values[3] = 10.0 * OUTLIER_THRESHOLD + 1.0;
+        let anomalies = detect(&series(&buckets, &values), &buckets);
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].date, buckets[3]);
+    }
+
+    #[test]
+    fn short_or_mostly_idle_series_are_skipped() {
+        let short = buckets(MIN_BUCKETS - 1);
+        let mut values = vec![10.0; short.len()];
+        values[0] = 1000.0;
+        assert!(detect(&series(&short, &values), &short).is_empty());
+
+        // Days without a value count as zero, leaving no typical day.
+        let buckets = buckets(10);
+        let idle = series(&buckets[..3], &[1.0, 500.0, 2.0]);
+        assert!(detect(&idle, &buckets).is_empty());
+    }
+}
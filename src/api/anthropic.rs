@@ -1,142 +1,111 @@
+use crate::api::paginate::{self, Paged};
+use crate::api::progress::Progress;
+use crate::api::retry::{self, RetryCounter};
 use crate::models::{
     AnthropicApiKeyResponse, AnthropicCostBucket, AnthropicCostResponse, AnthropicUsageResponse,
-    AnthropicUsageTimeBucket,
+    AnthropicUsageTimeBucket, BucketWidth,
 };
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
 use serde_json;
 
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+
 #[derive(Clone)]
 pub struct AnthropicClient {
     client: Client,
     api_key: String,
     base_url: String,
+    retries: RetryCounter,
+    progress: Progress,
 }
 
 impl AnthropicClient {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(api_key: String, base_url: &str) -> Self {
         Self {
-            client: Client::new(),
+            client: super::http_client(),
             api_key,
-            base_url: "https://api.anthropic.com/v1/organizations".to_string(),
+            base_url: format!("{}/v1/organizations", base_url.trim_end_matches('/')),
+            retries: RetryCounter::default(),
+            progress: Progress::default(),
+        }
+    }
+
+    /// Requests retried since the last call, across every clone of this client.
+    pub fn take_retries(&self) -> u32 {
+        self.retries.take()
+    }
+
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// Sends `request` with the auth headers and parses the JSON body.
+    async fn get_json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let request = request
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01");
+        let (status, text) = retry::send(request, &self.retries).await?;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("API error: {} - {}", status, text));
         }
+        serde_json::from_str(&text).context(format!(
+            "Failed to parse response. Status: {}. Response: {}",
+            status,
+            text.chars().take(500).collect::<String>()
+        ))
     }
 
-    pub async fn fetch_costs(&self, start_time: DateTime<Utc>) -> Result<Vec<AnthropicCostBucket>> {
+    pub async fn fetch_costs(
+        &self,
+        start_time: DateTime<Utc>,
+    ) -> Result<Paged<AnthropicCostBucket>> {
         let start = start_time.format("%Y-%m-%dT%H:%M:%SZ").to_string();
         let base_url = format!("{}/cost_report", self.base_url);
-        let mut all_data = Vec::new();
-        let mut page: Option<String> = None;
-        loop {
-            let mut req = self
-                .client
-                .get(&base_url)
-                .header("x-api-key", &self.api_key)
-                .header("anthropic-version", "2023-06-01")
-                .query(&[
-                    ("starting_at", start.as_str()),
-                    ("group_by[]", "description"),
-                ]);
-            if let Some(ref p) = page {
-                req = req.query(&[("page", p.as_str())]);
-            }
-            let response = req.send().await.context("Failed to send request")?;
-            let status = response.status();
-            let text = response
-                .text()
-                .await
-                .context("Failed to read response body")?;
-            if !status.is_success() {
-                return Err(anyhow::anyhow!("API error: {} - {}", status, text));
+        let costs = paginate::collect(|page| {
+            let mut req = self.client.get(&base_url).query(&[
+                ("starting_at", start.as_str()),
+                ("group_by[]", "description"),
+            ]);
+            if let Some(page) = page {
+                req = req.query(&[("page", page)]);
             }
-            let resp: AnthropicCostResponse = serde_json::from_str(&text).context(format!(
-                "Failed to parse response. Status: {}. Response: {}",
-                status,
-                text.chars().take(500).collect::<String>()
-            ))?;
-            all_data.extend(resp.data);
-            if !resp.has_more {
-                break;
-            }
-            page = resp.next_page;
-        }
-        Ok(all_data)
+            self.get_json::<AnthropicCostResponse>(req)
+        });
+        self.progress.track("costs", costs).await
     }
 
     pub async fn fetch_usage(
         &self,
         start_time: DateTime<Utc>,
-    ) -> Result<Vec<AnthropicUsageTimeBucket>> {
+        width: BucketWidth,
+    ) -> Result<Paged<AnthropicUsageTimeBucket>> {
         let start = start_time.format("%Y-%m-%dT%H:%M:%SZ").to_string();
         let base_url = format!("{}/usage_report/messages", self.base_url);
-        let mut all_data = Vec::new();
-        let mut page: Option<String> = None;
-        loop {
-            let mut req = self
-                .client
-                .get(&base_url)
-                .header("x-api-key", &self.api_key)
-                .header("anthropic-version", "2023-06-01")
-                .query(&[
-                    ("starting_at", start.as_str()),
-                    ("group_by[]", "model"),
-                    ("group_by[]", "api_key_id"),
-                    ("bucket_width", "1d"),
-                ]);
-            if let Some(ref p) = page {
-                req = req.query(&[("page", p.as_str())]);
-            }
-            let response = req.send().await.context("Failed to send request")?;
-            let status = response.status();
-            let text = response
-                .text()
-                .await
-                .context("Failed to read response body")?;
-            if !status.is_success() {
-                return Err(anyhow::anyhow!("API error: {} - {}", status, text));
-            }
-            let resp: AnthropicUsageResponse = serde_json::from_str(&text).context(format!(
-                "Failed to parse response. Status: {}. Response: {}",
-                status,
-                text.chars().take(500).collect::<String>()
-            ))?;
-            all_data.extend(resp.data);
-            if !resp.has_more {
-                break;
+        let usage = paginate::collect(|page| {
+            let mut req = self.client.get(&base_url).query(&[
+                ("starting_at", start.as_str()),
+                ("group_by[]", "model"),
+                ("group_by[]", "api_key_id"),
+                ("group_by[]", "service_tier"),
+                ("bucket_width", width.param()),
+            ]);
+            if let Some(page) = page {
+                req = req.query(&[("page", page)]);
             }
-            page = resp.next_page;
-        }
-        Ok(all_data)
+            self.get_json::<AnthropicUsageResponse>(req)
+        });
+        self.progress.track("usage", usage).await
     }
 
     pub async fn fetch_api_key_name(&self, api_key_id: &str) -> Result<String> {
         let url = format!("{}/api_keys/{}", self.base_url, api_key_id);
-        let response = self
-            .client
-            .get(&url)
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .send()
+        let resp: AnthropicApiKeyResponse = self
+            .get_json(self.client.get(&url))
             .await
             .context("Failed to fetch API key details")?;
-
-        let status = response.status();
-        let text = response
-            .text()
-            .await
-            .context("Failed to read response body")?;
-
-        if !status.is_success() {
-            return Err(anyhow::anyhow!("API error: {} - {}", status, text));
-        }
-
-        let resp: AnthropicApiKeyResponse = serde_json::from_str(&text).context(format!(
-            "Failed to parse API key response. Status: {}. Response: {}",
-            status,
-            text.chars().take(500).collect::<String>()
-        ))?;
-
         Ok(resp.name)
     }
 }
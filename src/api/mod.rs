@@ -1,2 +1,23 @@
 pub mod anthropic;
 pub mod openai;
+pub mod paginate;
+pub mod progress;
+pub mod retry;
+
+use reqwest::Client;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// Longest silence while waiting on a response before the request fails and
+// is retried; large reports stream in well within this.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// HTTP client for the API clients, with timeouts so a hung connection fails
+/// instead of leaving a refresh running forever.
+pub fn http_client() -> Client {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .build()
+        .expect("failed to initialize the HTTP client")
+}
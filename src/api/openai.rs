@@ -1,146 +1,157 @@
+use crate::api::paginate::{self, Paged};
+use crate::api::progress::Progress;
+use crate::api::retry::{self, RetryCounter};
 use crate::models::{
-    OpenAIBucket, OpenAICostResponse, OpenAICostResult, OpenAIProjectApiKey,
-    OpenAIProjectApiKeysResponse, OpenAIProjectsResponse, OpenAIUsageResponse,
+    BucketWidth, OpenAIBucket, OpenAICostResponse, OpenAICostResult, OpenAIProject,
+    OpenAIProjectApiKey, OpenAIProjectApiKeysResponse, OpenAIProjectsResponse, OpenAIUsageBucket,
+    OpenAIUsageResponse,
 };
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
 use std::collections::{HashMap, HashSet};
+use tokio::task::JoinSet;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com";
 
 #[derive(Clone)]
 pub struct OpenAIClient {
     client: Client,
     api_key: String,
     base_url: String,
+    retries: RetryCounter,
+    progress: Progress,
 }
 
 impl OpenAIClient {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(api_key: String, base_url: &str) -> Self {
         Self {
-            client: Client::new(),
+            client: super::http_client(),
             api_key,
-            base_url: "https://api.openai.com/v1/organization".to_string(),
+            base_url: format!("{}/v1/organization", base_url.trim_end_matches('/')),
+            retries: RetryCounter::default(),
+            progress: Progress::default(),
         }
     }
 
-    pub async fn fetch_costs(
-        &self,
-        start_time: DateTime<Utc>,
-    ) -> Result<Vec<OpenAIBucket<OpenAICostResult>>> {
-        let start_ts = start_time.timestamp();
-        let mut all_buckets = Vec::new();
-        let mut page: Option<String> = None;
-
-        for _ in 0..30 {
-            let mut params: Vec<(&str, String)> = vec![
-                ("start_time", start_ts.to_string()),
-                ("group_by", "line_item".to_string()),
-                ("limit", "180".to_string()),
-            ];
-            if let Some(ref p) = page {
-                params.push(("page", p.clone()));
-            }
-
-            let response = self
-                .client
-                .get(format!("{}/costs", self.base_url))
-                .query(&params)
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .send()
-                .await
-                .context("Failed to fetch costs")?;
-
-            let status = response.status();
-            let text = response.text().await.context("Failed to read response")?;
+    /// Requests retried since the last call, across every clone of this client.
+    pub fn take_retries(&self) -> u32 {
+        self.retries.take()
+    }
 
-            if !status.is_success() {
-                return Err(anyhow::anyhow!("API error: {} - {}", status, text));
-            }
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
 
-            let resp: OpenAICostResponse = serde_json::from_str(&text).context(format!(
-                "Parse error: {}",
-                text.chars().take(200).collect::<String>()
-            ))?;
+    /// Sends `request` and parses the JSON body; `what` names the data in errors.
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        what: &str,
+    ) -> Result<T> {
+        let request = request.header("Authorization", format!("Bearer {}", self.api_key));
+        let (status, text) = retry::send(request, &self.retries)
+            .await
+            .context(format!("Failed to fetch {}", what))?;
 
-            all_buckets.extend(resp.data);
-            if !resp.has_more {
-                break;
-            }
-            page = resp.next_page;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(
+                "API error for {}: {} - {}",
+                what,
+                status,
+                text
+            ));
         }
 
-        Ok(all_buckets)
+        serde_json::from_str(&text).context(format!(
+            "Failed to parse {} response: {}",
+            what,
+            text.chars().take(200).collect::<String>()
+        ))
     }
 
-    async fn fetch_usage_endpoint(
+    pub async fn fetch_costs(
         &self,
-        endpoint: &str,
-        start_ts: i64,
-    ) -> Result<Vec<crate::models::OpenAIUsageBucket>> {
-        let mut all_buckets = Vec::new();
-        let mut page: Option<String> = None;
-
-        for _ in 0..30 {
-            let mut params: Vec<(&str, String)> = vec![
-                ("start_time", start_ts.to_string()),
-                ("interval", "1d".to_string()),
-                ("group_by", "model".to_string()),
-                ("group_by", "api_key_id".to_string()),
-            ];
-            if let Some(ref p) = page {
-                params.push(("page", p.clone()));
-            }
-
-            let response = self
-                .client
-                .get(format!("{}/usage/{}", self.base_url, endpoint))
-                .query(&params)
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .send()
-                .await
-                .context(format!("Failed to fetch {} usage", endpoint))?;
-
-            let status = response.status();
-            let text = response.text().await.context("Failed to read response")?;
-
-            if !status.is_success() {
-                return Err(anyhow::anyhow!(
-                    "API error for {}: {} - {}",
-                    endpoint,
-                    status,
-                    text
-                ));
+        start_time: DateTime<Utc>,
+    ) -> Result<Paged<OpenAIBucket<OpenAICostResult>>> {
+        let start_ts = start_time.timestamp().to_string();
+        let url = format!("{}/costs", self.base_url);
+
+        let costs = paginate::collect(|page| {
+            let mut request = self.client.get(&url).query(&[
+                ("start_time", start_ts.as_str()),
+                ("group_by", "line_item"),
+                ("limit", "180"),
+            ]);
+            if let Some(page) = page {
+                request = request.query(&[("page", page)]);
             }
+            self.get_json::<OpenAICostResponse>(request, "costs")
+        });
+        self.progress.track("costs", costs).await
+    }
 
-            let resp: OpenAIUsageResponse = serde_json::from_str(&text).context(format!(
-                "Failed to parse {} usage response: {}",
-                endpoint,
-                text.chars().take(200).collect::<String>()
-            ))?;
-
-            all_buckets.extend(resp.data);
-            if !resp.has_more {
-                break;
-            }
-            page = resp.next_page;
+    /// Request for one page of `endpoint`'s usage in buckets of `width`.
+    fn usage_request(
+        &self,
+        endpoint: &str,
+        start_ts: &str,
+        width: BucketWidth,
+        page: Option<String>,
+    ) -> RequestBuilder {
+        let url = format!("{}/usage/{}", self.base_url, endpoint);
+        let mut request = self.client.get(url).query(&[
+            ("start_time", start_ts),
+            ("bucket_width", width.param()),
+            ("group_by", "model"),
+            ("group_by", "api_key_id"),
+        ]);
+        // Only completions can be batched; the other endpoints reject it.
+        if endpoint == "completions" {
+            request = request.query(&[("group_by", "batch")]);
+        }
+        if let Some(page) = page {
+            request = request.query(&[("page", page)]);
         }
+        request
+    }
 
-        Ok(all_buckets)
+    async fn fetch_usage_endpoint(
+        &self,
+        endpoint: &'static str,
+        start_ts: i64,
+        width: BucketWidth,
+    ) -> Result<Paged<OpenAIUsageBucket>> {
+        let start_ts = start_ts.to_string();
+        let what = format!("{} usage", endpoint);
+        let what = what.as_str();
+
+        let usage = paginate::collect(|page| {
+            let request = self.usage_request(endpoint, &start_ts, width, page);
+            self.get_json::<OpenAIUsageResponse>(request, what)
+        });
+        self.progress.track(endpoint, usage).await
     }
 
+    /// Usage across the completions, embeddings and images endpoints, with
+    /// why each incomplete one was cut short. An endpoint that fails is
+    /// skipped as long as another one answered.
     pub async fn fetch_usage(
         &self,
         start_time: DateTime<Utc>,
-    ) -> Result<Vec<crate::models::OpenAIUsageBucket>> {
+        width: BucketWidth,
+    ) -> Result<(Vec<OpenAIUsageBucket>, Vec<String>)> {
         let start_ts = start_time.timestamp();
 
         let (completions_result, embeddings_result, images_result) = tokio::join!(
-            self.fetch_usage_endpoint("completions", start_ts),
-            self.fetch_usage_endpoint("embeddings", start_ts),
-            self.fetch_usage_endpoint("images", start_ts),
+            self.fetch_usage_endpoint("completions", start_ts, width),
+            self.fetch_usage_endpoint("embeddings", start_ts, width),
+            self.fetch_usage_endpoint("images", start_ts, width),
         );
 
         let mut all_buckets = Vec::new();
+        let mut truncations = Vec::new();
         let mut endpoint_errors = Vec::new();
 
         for (result, name) in [
@@ -149,7 +160,12 @@ impl OpenAIClient {
             (images_result, "images"),
         ] {
             match result {
-                Ok(mut buckets) => all_buckets.append(&mut buckets),
+                Ok(mut paged) => {
+                    all_buckets.append(&mut paged.items);
+                    if let Some(truncation) = paged.truncated {
+                        truncations.push(format!("{}: {}", name, truncation));
+                    }
+                }
                 Err(e) => endpoint_errors.push(format!("{}: {}", name, e)),
             }
         }
@@ -161,129 +177,61 @@ impl OpenAIClient {
             ));
         }
 
-        Ok(all_buckets)
+        Ok((all_buckets, truncations))
     }
 
-    pub async fn fetch_projects(&self) -> Result<Vec<crate::models::OpenAIProject>> {
-        let mut all_projects = Vec::new();
-        let mut after: Option<String> = None;
-
-        loop {
-            let mut url = format!("{}/projects", self.base_url);
-            if let Some(ref a) = after {
-                url = format!("{}?after={}", url, a);
-            }
-
-            let response = self
-                .client
-                .get(&url)
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .header("Content-Type", "application/json")
-                .send()
-                .await
-                .context("Failed to fetch projects")?;
-
-            let status = response.status();
-            let text = response.text().await.context("Failed to read response")?;
-
-            if !status.is_success() {
-                return Err(anyhow::anyhow!("API error: {} - {}", status, text));
-            }
-
-            let resp: OpenAIProjectsResponse = serde_json::from_str(&text).context(format!(
-                "Failed to parse projects response: {}",
-                text.chars().take(200).collect::<String>()
-            ))?;
-
-            all_projects.extend(resp.data);
+    pub async fn fetch_projects(&self) -> Result<Paged<OpenAIProject>> {
+        let url = format!("{}/projects", self.base_url);
 
-            if !resp.has_more {
-                break;
+        paginate::collect(|after| {
+            let mut request = self.client.get(&url);
+            if let Some(after) = after {
+                request = request.query(&[("after", after)]);
             }
-
-            after = resp.last_id;
-        }
-
-        Ok(all_projects)
+            self.get_json::<OpenAIProjectsResponse>(request, "projects")
+        })
+        .await
     }
 
     async fn fetch_api_keys_for_project(
         &self,
         project_id: &str,
-    ) -> Result<Vec<OpenAIProjectApiKey>> {
-        let mut all_api_keys = Vec::new();
-        let mut after: Option<String> = None;
-
-        loop {
-            let mut url = format!("{}/projects/{}/api_keys", self.base_url, project_id);
-            if let Some(ref a) = after {
-                url = format!("{}?after={}", url, a);
-            }
-
-            let response = self
-                .client
-                .get(&url)
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .header("Content-Type", "application/json")
-                .send()
-                .await
-                .context(format!(
-                    "Failed to fetch API keys for project {}",
-                    project_id
-                ))?;
-
-            let status = response.status();
-            let text = response.text().await.context("Failed to read response")?;
-
-            if !status.is_success() {
-                return Err(anyhow::anyhow!("API error: {} - {}", status, text));
+    ) -> Result<Paged<OpenAIProjectApiKey>> {
+        let url = format!("{}/projects/{}/api_keys", self.base_url, project_id);
+        let what = format!("API keys for project {}", project_id);
+        let what = what.as_str();
+
+        paginate::collect(|after| {
+            let mut request = self.client.get(&url);
+            if let Some(after) = after {
+                request = request.query(&[("after", after)]);
             }
-
-            let resp: OpenAIProjectApiKeysResponse =
-                serde_json::from_str(&text).context(format!(
-                    "Failed to parse API keys response for project {}",
-                    project_id
-                ))?;
-
-            all_api_keys.extend(resp.data);
-
-            if !resp.has_more {
-                break;
-            }
-
-            if let Some(id) = resp.last_id {
-                after = Some(id);
-            } else {
-                break;
-            }
-        }
-
-        Ok(all_api_keys)
+            self.get_json::<OpenAIProjectApiKeysResponse>(request, what)
+        })
+        .await
     }
 
     pub async fn fetch_api_key_names_for_ids(
         &self,
         api_key_ids: &[String],
     ) -> Result<HashMap<String, String>> {
-        let projects = self
-            .fetch_projects()
-            .await
-            .context("Failed to fetch projects")?;
+        // Names are only cosmetic: keys on projects past a truncated list
+        // fall back to their abbreviated id.
+        let projects = self.fetch_projects().await?.items;
         let api_key_ids_set: HashSet<&String> = api_key_ids.iter().collect();
 
-        let fetch_tasks: Vec<_> = projects
-            .iter()
-            .map(|project| {
-                let client = self.clone();
-                let project_id = project.id.clone();
-                tokio::spawn(async move { client.fetch_api_keys_for_project(&project_id).await })
-            })
-            .collect();
+        // Dropping the set aborts the lookups, so they stop with the fetch.
+        let mut lookups = JoinSet::new();
+        for project in &projects {
+            let client = self.clone();
+            let project_id = project.id.clone();
+            lookups.spawn(async move { client.fetch_api_keys_for_project(&project_id).await });
+        }
 
         let mut api_key_map = HashMap::new();
-        for task in fetch_tasks {
-            if let Ok(Ok(api_keys)) = task.await {
-                for api_key in api_keys {
+        while let Some(result) = lookups.join_next().await {
+            if let Ok(Ok(api_keys)) = result {
+                for api_key in api_keys.items {
                     if api_key_ids_set.contains(&api_key.id) {
                         api_key_map
                             .insert(api_key.id.clone(), api_key.name.clone().unwrap_or_default());
@@ -295,3 +243,43 @@ impl OpenAIClient {
         Ok(api_key_map)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(request: RequestBuilder) -> Vec<(String, String)> {
+        let request = request.build().unwrap();
+        request.url().query_pairs().into_owned().collect()
+    }
+
+    #[test]
+    fn usage_request_sets_bucket_width() {
+        let client = OpenAIClient::new("key".to_string(), DEFAULT_BASE_URL);
+        for (width, param) in [
+            (BucketWidth::Day, "1d"),
+            (BucketWidth::Hour, "1h"),
+            (BucketWidth::Minute, "1m"),
+        ] {
+            let pairs = query(client.usage_request("embeddings", "1700000000", width, None));
+            assert!(pairs.contains(&("bucket_width".to_string(), param.to_string())));
+            assert!(pairs.iter().all(|(key, _)| key != "interval"));
+            assert!(!pairs.contains(&("group_by".to_string(), "batch".to_string())));
+        }
+    }
+
+    #[test]
+    fn usage_request_groups_completions_by_batch() {
+        let client = OpenAIClient::new("key".to_string(), DEFAULT_BASE_URL);
+        let request = client.usage_request(
+            "completions",
+            "1700000000",
+            BucketWidth::Hour,
+            Some("next".to_string()),
+        );
+        let pairs = query(request);
+        assert!(pairs.contains(&("group_by".to_string(), "batch".to_string())));
+        assert!(pairs.contains(&("page".to_string(), "next".to_string())));
+        assert!(pairs.contains(&("start_time".to_string(), "1700000000".to_string())));
+    }
+}
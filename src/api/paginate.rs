@@ -0,0 +1,287 @@
+use crate::models::{
+    AnthropicCostBucket, AnthropicCostResponse, AnthropicUsageResponse, AnthropicUsageTimeBucket,
+    OpenAIBucket, OpenAICostResponse, OpenAICostResult, OpenAIProject, OpenAIProjectApiKey,
+    OpenAIProjectApiKeysResponse, OpenAIProjectsResponse, OpenAIUsageBucket, OpenAIUsageResponse,
+};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+
+/// Pages followed per list before the rest is reported as truncated.
+pub const MAX_PAGES: usize = 100;
+
+/// One page of a list endpoint.
+pub trait Page {
+    type Item;
+
+    fn has_more(&self) -> bool;
+
+    /// What to request the next page with: a page token or the id of the last
+    /// item, depending on the endpoint.
+    fn next_cursor(&self) -> Option<String>;
+
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+/// Why a list stopped before its last page.
+#[derive(Clone, Copy, Debug)]
+pub enum Truncation {
+    PageLimit,
+    /// The API handed out a cursor it had already returned, so following it
+    /// would loop.
+    RepeatedCursor,
+    /// The API said there was more but gave no cursor to get it with.
+    MissingCursor,
+}
+
+impl fmt::Display for Truncation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Truncation::PageLimit => write!(f, "stopped after {} pages", MAX_PAGES),
+            Truncation::RepeatedCursor => write!(f, "the API repeated a page cursor"),
+            Truncation::MissingCursor => write!(f, "the API reported more pages without a cursor"),
+        }
+    }
+}
+
+pub struct Paged<T> {
+    pub items: Vec<T>,
+    /// `Some` when `items` is missing later pages.
+    pub truncated: Option<Truncation>,
+}
+
+/// Follows a list endpoint page by page. `fetch_page` is called with the
+/// cursor of the next page, `None` for the first one.
+pub async fn collect<P, F, Fut>(mut fetch_page: F) -> Result<Paged<P::Item>>
+where
+    P: Page,
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<P>>,
+{
+    let mut items = Vec::new();
+    let mut seen = HashSet::new();
+    let mut cursor = None;
+
+    for _ in 0..MAX_PAGES {
+        let page = fetch_page(cursor.take()).await?;
+        let has_more = page.has_more();
+        let next = page.next_cursor();
+        items.extend(page.into_items());
+
+        if !has_more {
+            return Ok(Paged {
+                items,
+                truncated: None,
+            });
+        }
+        let truncated = match next {
+            None => Truncation::MissingCursor,
+            Some(next) if !seen.insert(next.clone()) => Truncation::RepeatedCursor,
+            Some(next) => {
+                cursor = Some(next);
+                continue;
+            }
+        };
+        return Ok(Paged {
+            items,
+            truncated: Some(truncated),
+        });
+    }
+
+    Ok(Paged {
+        items,
+        truncated: Some(Truncation::PageLimit),
+    })
+}
+
+impl Page for OpenAICostResponse {
+    type Item = OpenAIBucket<OpenAICostResult>;
+
+    fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    fn next_cursor(&self) -> Option<String> {
+        self.next_page.clone()
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+impl Page for OpenAIUsageResponse {
+    type Item = OpenAIUsageBucket;
+
+    fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    fn next_cursor(&self) -> Option<String> {
+        self.next_page.clone()
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+impl Page for OpenAIProjectsResponse {
+    type Item = OpenAIProject;
+
+    fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    fn next_cursor(&self) -> Option<String> {
+        self.last_id.clone()
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+impl Page for OpenAIProjectApiKeysResponse {
+    type Item = OpenAIProjectApiKey;
+
+    fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    fn next_cursor(&self) -> Option<String> {
+        self.last_id.clone()
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+impl Page for AnthropicCostResponse {
+    type Item = AnthropicCostBucket;
+
+    fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    fn next_cursor(&self) -> Option<String> {
+        self.next_page.clone()
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+impl Page for AnthropicUsageResponse {
+    type Item = AnthropicUsageTimeBucket;
+
+    fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    fn next_cursor(&self) -> Option<String> {
+        self.next_page.clone()
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestPage {
+        items: Vec<usize>,
+        has_more: bool,
+        cursor: Option<String>,
+    }
+
+    impl Page for TestPage {
+        type Item = usize;
+
+        fn has_more(&self) -> bool {
+            self.has_more
+        }
+
+        fn next_cursor(&self) -> Option<String> {
+            self.cursor.clone()
+        }
+
+        fn into_items(self) -> Vec<Self::Item> {
+            self.items
+        }
+    }
+
+    #[tokio::test]
+    async fn follows_cursors_until_the_last_page() {
+        let mut requested = Vec::new();
+        let paged = collect(|cursor: Option<String>| {
+            requested.push(cursor.clone());
+            let page = cursor.map_or(0, |c| c.parse::<usize>().unwrap());
+            async move {
+                Ok(TestPage {
+                    items: vec![page],
+                    has_more: page < 2,
+                    cursor: Some((page + 1).to_string()),
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(paged.items, vec![0, 1, 2]);
+        assert!(paged.truncated.is_none());
+        assert_eq!(
+            requested,
+            vec![None, Some("1".to_string()), Some("2".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_at_a_repeated_cursor() {
+        let mut pages = 0;
+        let paged = collect(|_| {
+            pages += 1;
+            async {
+                Ok(TestPage {
+                    items: vec![1],
+                    has_more: true,
+                    cursor: Some("same".to_string()),
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(pages, 2);
+        assert_eq!(paged.items, vec![1, 1]);
+        assert!(matches!(paged.truncated, Some(Truncation::RepeatedCursor)));
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_pages() {
+        let mut pages = 0;
+        let paged = collect(|_| {
+            pages += 1;
+            let cursor = pages.to_string();
+            async move {
+                Ok(TestPage {
+                    items: vec![1],
+                    has_more: true,
+                    cursor: Some(cursor),
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(pages, MAX_PAGES);
+        assert_eq!(paged.items.len(), MAX_PAGES);
+        assert!(matches!(paged.truncated, Some(Truncation::PageLimit)));
+    }
+}
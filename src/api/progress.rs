@@ -0,0 +1,60 @@
+use anyhow::Result;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StepStatus {
+    Pending,
+    Done,
+    Failed,
+}
+
+/// Status of each endpoint the current fetch has called, in the order they
+/// were started. Shared by every clone of a client so the UI can follow a
+/// fetch while it runs.
+#[derive(Clone, Default)]
+pub struct Progress(Arc<Mutex<Steps>>);
+
+pub type Steps = Vec<(&'static str, StepStatus)>;
+
+impl Progress {
+    /// Forgets the steps of the previous fetch.
+    pub fn reset(&self) {
+        self.steps().clear();
+    }
+
+    pub fn snapshot(&self) -> Steps {
+        self.steps().clone()
+    }
+
+    /// Runs `step`, marking it pending until it resolves.
+    pub async fn track<T>(
+        &self,
+        name: &'static str,
+        step: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        self.set(name, StepStatus::Pending);
+        let result = step.await;
+        let status = if result.is_ok() {
+            StepStatus::Done
+        } else {
+            StepStatus::Failed
+        };
+        self.set(name, status);
+        result
+    }
+
+    fn set(&self, name: &'static str, status: StepStatus) {
+        let mut steps = self.steps();
+        match steps.iter_mut().find(|(step, _)| *step == name) {
+            Some(step) => step.1 = status,
+            None => steps.push((name, status)),
+        }
+    }
+
+    fn steps(&self) -> MutexGuard<'_, Steps> {
+        // The lock is only held to copy a few entries, so a poisoned one
+        // still holds consistent data.
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
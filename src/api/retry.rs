@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, StatusCode};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Attempts after the first one before a transient failure is returned.
+const MAX_RETRIES: u32 = 4;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+// A server asking for a longer wait than this gets its error reported
+// instead; the next refresh tries again.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Number of retried requests, shared by every clone of a client so a whole
+/// fetch can be reported at once.
+#[derive(Clone, Default)]
+pub struct RetryCounter(Arc<AtomicU32>);
+
+impl RetryCounter {
+    /// Returns the count so far and starts over from zero.
+    pub fn take(&self) -> u32 {
+        self.0.swap(0, Ordering::Relaxed)
+    }
+
+    fn record(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+struct Reply {
+    status: StatusCode,
+    text: String,
+    retry_after: Option<Duration>,
+}
+
+/// Sends `request` and reads the body, retrying 429s, 5xx responses and
+/// connection errors with jittered exponential backoff, or after the
+/// `Retry-After` delay when the server gives one. Other non-2xx responses
+/// are returned for the caller to report.
+pub async fn send(request: RequestBuilder, retries: &RetryCounter) -> Result<(StatusCode, String)> {
+    let mut attempt = 0;
+    loop {
+        let request = request.try_clone().context("Request can't be retried")?;
+        let outcome = send_once(request).await;
+
+        let delay = match &outcome {
+            Ok(reply) if !is_transient(reply.status) => None,
+            Ok(reply) => match reply.retry_after {
+                Some(delay) => Some(delay).filter(|delay| *delay <= MAX_RETRY_AFTER),
+                None => Some(backoff(attempt)),
+            },
+            Err(e) if e.is_builder() => None,
+            Err(_) => Some(backoff(attempt)),
+        };
+
+        match delay {
+            Some(delay) if attempt < MAX_RETRIES => {
+                retries.record();
+                attempt += 1;
+                tokio::time::sleep(delay).await;
+            }
+            _ => {
+                let reply = outcome.context("Failed to send request")?;
+                return Ok((reply.status, reply.text));
+            }
+        }
+    }
+}
+
+async fn send_once(request: RequestBuilder) -> reqwest::Result<Reply> {
+    let response = request.send().await?;
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_retry_after);
+    let text = response.text().await?;
+    Ok(Reply {
+        status,
+        text,
+        retry_after,
+    })
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// `Retry-After` is either a number of seconds or an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = at.with_timezone(&Utc) - Utc::now();
+    // A date in the past means "now".
+    Some(wait.to_std().unwrap_or_default())
+}
+
+/// Doubles with every attempt, then waits a random 50-100% of that so
+/// lookups that failed together don't retry together.
+fn backoff(attempt: u32) -> Duration {
+    let delay = MAX_DELAY.min(BASE_DELAY * 2u32.pow(attempt));
+    delay.mul_f64(0.5 + fastrand::f64() * 0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_after_in_seconds() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_as_http_date() {
+        let at = Utc::now() + chrono::Duration::seconds(30);
+        let header = at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        let wait = parse_retry_after(&header).unwrap();
+        assert!(wait > Duration::from_secs(25) && wait <= Duration::from_secs(30));
+
+        let past = parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(past, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_garbage() {
+        assert_eq!(parse_retry_after(""), None);
+        assert_eq!(parse_retry_after("soon"), None);
+        assert_eq!(parse_retry_after("-5"), None);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        for attempt in 0..=MAX_RETRIES + 6 {
+            let delay = backoff(attempt);
+            let full = MAX_DELAY.min(BASE_DELAY * 2u32.pow(attempt));
+            assert!(delay >= full / 2 && delay <= full);
+            assert!(delay <= MAX_DELAY);
+        }
+        assert!(backoff(10) >= MAX_DELAY / 2);
+    }
+}
@@ -1,42 +1,272 @@
-use crate::api::{anthropic::AnthropicClient, openai::OpenAIClient};
-use crate::models::{DailyData, DailyUsageData};
-use crate::provider::{Provider, ProviderClient, ProviderInfo};
-use chrono::Duration;
+use crate::anomaly::{self, Anomaly};
+use crate::api::progress::Steps;
+use crate::budget::{Budget, BudgetStatus, Period};
+use crate::cache::{self, CacheEntry};
+use crate::fetch;
+use crate::forecast::{self, Forecast};
+use crate::models::{BucketWidth, DailyData, DailyUsageData};
+use crate::pricing::{CostEstimate, Pricing};
+use crate::reconcile::{self, Reconciliation};
+use crate::provider::{Account, InFlightFetch, ProviderId, ProviderInfo, UsageProvider};
+use crate::ui::colors::ColorPalette;
+use crate::ui::content::shared;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
 use crossterm::event::KeyCode;
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::task::AbortHandle;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum View {
     Cost,
     Usage,
+    Live,
+    /// Billed cost against token-derived estimates.
+    Reconcile,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum GroupBy {
     Model,
     ApiKeys,
+    /// Combined view only: one series per provider.
+    Provider,
+    /// Combined view only: one series per provider and model.
+    ProviderModel,
+}
+
+/// What the charts and summary show: a single account, or several added
+/// together.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Provider(ProviderId),
+    /// Every connected account of the provider with this id.
+    Accounts(&'static str),
+    /// Every connected account of every provider.
+    All,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Range {
+    Last24Hours,
+    Last72Hours,
     SevenDays,
     ThirtyDays,
+    NinetyDays,
+    MonthToDate,
+    PreviousMonth,
+    Custom,
 }
 
 impl Range {
+    pub const ALL: [Range; 8] = [
+        Range::Last24Hours,
+        Range::Last72Hours,
+        Range::SevenDays,
+        Range::ThirtyDays,
+        Range::NinetyDays,
+        Range::MonthToDate,
+        Range::PreviousMonth,
+        Range::Custom,
+    ];
+
     pub fn label(self) -> &'static str {
         match self {
+            Range::Last24Hours => "24h",
+            Range::Last72Hours => "72h",
             Range::SevenDays => "7d",
             Range::ThirtyDays => "30d",
+            Range::NinetyDays => "90d",
+            Range::MonthToDate => "MTD",
+            Range::PreviousMonth => "Last month",
+            Range::Custom => "Custom",
+        }
+    }
+
+    pub fn bucket_width(self) -> BucketWidth {
+        match self {
+            Range::Last24Hours | Range::Last72Hours => BucketWidth::Hour,
+            _ => BucketWidth::Day,
         }
     }
 
-    pub fn days(self) -> i64 {
+    /// Length of the hourly ranges.
+    fn rolling_hours(self) -> Option<i64> {
         match self {
-            Range::SevenDays => 7,
-            Range::ThirtyDays => 30,
+            Range::Last24Hours => Some(24),
+            Range::Last72Hours => Some(fetch::HOURS_TO_FETCH),
+            _ => None,
+        }
+    }
+
+    /// Length of rolling ranges; calendar ranges have no fixed length.
+    fn rolling_days(self) -> Option<i64> {
+        match self {
+            Range::SevenDays => Some(7),
+            Range::ThirtyDays => Some(30),
+            Range::NinetyDays => Some(90),
+            _ => None,
+        }
+    }
+}
+
+/// Inclusive span of calendar days (UTC) covered by a range.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct DateWindow {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateWindow {
+    pub fn ending(end: NaiveDate, days: i64) -> Self {
+        Self {
+            start: end - Duration::days(days.max(1) - 1),
+            end,
+        }
+    }
+
+    /// From the first of `today`'s month through `today`.
+    pub fn month_to_date(today: NaiveDate) -> Self {
+        Self {
+            start: today.with_day(1).unwrap(),
+            end: today,
+        }
+    }
+
+    /// The whole calendar month before `today`'s.
+    pub fn previous_month(today: NaiveDate) -> Self {
+        let end = today.with_day(1).unwrap() - Duration::days(1);
+        Self {
+            start: end.with_day(1).unwrap(),
+            end,
+        }
+    }
+
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days() + 1
+    }
+
+    /// The window of the same length immediately before this one.
+    pub fn previous(&self) -> Self {
+        Self::ending(self.start - Duration::days(1), self.days())
+    }
+
+    pub fn start_time(&self) -> DateTime<Utc> {
+        self.start.and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+}
+
+/// The buckets shown along the chart axis for the selected range.
+#[derive(Clone, Copy)]
+pub struct BucketWindow {
+    pub start: DateTime<Utc>,
+    pub len: i64,
+    pub width: BucketWidth,
+}
+
+impl BucketWindow {
+    pub fn from_dates(window: DateWindow) -> Self {
+        Self {
+            start: window.start_time(),
+            len: window.days(),
+            width: BucketWidth::Day,
+        }
+    }
+
+    /// The last `len` buckets of `width`, including the current one.
+    pub fn ending_now(width: BucketWidth, len: i64) -> Self {
+        Self::ending_at(width, len, Utc::now())
+    }
+
+    /// `len` buckets of `width`, the last one containing `at`.
+    pub fn ending_at(width: BucketWidth, len: i64, at: DateTime<Utc>) -> Self {
+        let current = width.truncate(at);
+        Self {
+            start: current - width.duration() * (len - 1) as i32,
+            len,
+            width,
+        }
+    }
+
+    /// End of the last bucket (exclusive).
+    pub fn end(&self) -> DateTime<Utc> {
+        self.start + self.width.duration() * self.len as i32
+    }
+
+    pub fn contains(&self, date: DateTime<Utc>) -> bool {
+        date >= self.start && date < self.end()
+    }
+
+    /// The window of the same length immediately before this one.
+    pub fn previous(&self) -> Self {
+        Self {
+            start: self.start - self.width.duration() * self.len as i32,
+            ..*self
         }
     }
+
+    /// Start of every bucket in the window, oldest first.
+    pub fn buckets(&self) -> impl Iterator<Item = DateTime<Utc>> {
+        let (start, step) = (self.start, self.width.duration());
+        (0..self.len).map(move |i| start + step * i as i32)
+    }
+
+    /// Chart axis label of the bucket `date` falls in.
+    pub fn label(&self, date: DateTime<Utc>) -> String {
+        date.format(self.width.label_format()).to_string()
+    }
+}
+
+// Chart slots are labelled by month and day, so a window must not reach the
+// same date of the next year: 365 days counting both ends.
+const MAX_CUSTOM_RANGE_DAYS: i64 = 365;
+
+// Full minutes of live throughput the burn rate is estimated from.
+pub const BURN_RATE_MINUTES: i64 = 15;
+
+// Failed refreshes double the delay up to this many times (32x the interval).
+const MAX_BACKOFF_DOUBLINGS: u32 = 5;
+const MAX_BACKOFF: Duration = Duration::hours(1);
+
+/// Timer state for `--refresh-interval`.
+pub struct AutoRefresh {
+    pub interval: Duration,
+    pub next_at: Option<DateTime<Utc>>,
+    /// Refreshes that failed in a row; each one doubles the delay.
+    pub failures: u32,
+}
+
+impl AutoRefresh {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_at: None,
+            failures: 0,
+        }
+    }
+
+    fn schedule(&mut self, finished_at: DateTime<Utc>, failed: bool) {
+        self.failures = if failed { self.failures + 1 } else { 0 };
+        self.next_at = Some(finished_at + self.delay());
+    }
+
+    fn delay(&self) -> Duration {
+        let factor = 1 << self.failures.min(MAX_BACKOFF_DOUBLINGS);
+        if self.failures == 0 {
+            self.interval
+        } else {
+            (self.interval * factor).min(MAX_BACKOFF.max(self.interval))
+        }
+    }
+}
+
+/// Text typed into the custom range popup.
+pub struct CustomRangeInput {
+    pub start: String,
+    pub end: String,
+    pub editing_end: bool,
+    pub error: Option<String>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -48,14 +278,19 @@ pub enum OptionsColumn {
 }
 
 pub struct App {
-    pub providers: HashMap<Provider, ProviderInfo>,
-    pub loading: bool,
-    pub selected_provider: Provider,
+    pub providers: Vec<ProviderInfo>,
+    pub selected_provider: ProviderId,
+    /// Set while an aggregate entry of the Providers column is selected.
+    /// `selected_provider` keeps the last single account so switching back
+    /// is one step.
+    pub aggregate: Option<Scope>,
     pub options_column: OptionsColumn,
     pub current_view: View,
     pub group_by: GroupBy,
     pub range: Range,
-    pub api_key_popup_active: Option<Provider>,
+    pub custom_range: DateWindow,
+    pub custom_range_input: Option<CustomRangeInput>,
+    pub api_key_popup_active: Option<ProviderId>,
     pub api_key_input: String,
     pub animation_frame: u32,
     pub group_by_expanded: bool,
@@ -63,21 +298,33 @@ pub struct App {
     pub filter_cursor_index: usize,
     pub chart_scrollbar_visible: bool,
     pub show_segment_values: bool,
+    pub show_anomalies: bool,
+    pub auto_refresh: Option<AutoRefresh>,
+    /// When the last refresh finished, successful or not.
+    pub last_refresh: Option<DateTime<Utc>>,
+    pub combined_cost_chart_scroll: usize,
+    pub combined_usage_chart_scroll: usize,
+    /// Valid budgets from the config file.
+    pub budgets: Vec<Budget>,
+    /// Built-in and configured model prices.
+    pub pricing: Pricing,
+    /// Last generation handed to a fetch; see `InFlightFetch`.
+    fetch_generation: u64,
 }
 
 impl App {
-    pub fn new() -> Self {
-        let mut providers = HashMap::new();
-        providers.insert(Provider::OpenAI, ProviderInfo::new());
-        providers.insert(Provider::Anthropic, ProviderInfo::new());
+    pub fn new(accounts: Vec<Account>) -> Self {
+        let providers = accounts.into_iter().map(ProviderInfo::new).collect();
         Self {
             providers,
-            loading: false,
-            selected_provider: Provider::OpenAI,
+            selected_provider: ProviderId(0),
+            aggregate: None,
             options_column: OptionsColumn::Provider,
             current_view: View::Usage,
             group_by: GroupBy::Model,
             range: Range::SevenDays,
+            custom_range: DateWindow::ending(Utc::now().date_naive(), 14),
+            custom_range_input: None,
             api_key_popup_active: None,
             api_key_input: String::new(),
             animation_frame: 0,
@@ -86,6 +333,14 @@ impl App {
             filter_cursor_index: 0,
             chart_scrollbar_visible: false,
             show_segment_values: false,
+            show_anomalies: false,
+            auto_refresh: None,
+            last_refresh: None,
+            combined_cost_chart_scroll: usize::MAX,
+            combined_usage_chart_scroll: usize::MAX,
+            budgets: Vec::new(),
+            pricing: Pricing::default(),
+            fetch_generation: 0,
         }
     }
 
@@ -112,35 +367,47 @@ impl App {
     pub fn move_column_cursor(&mut self, delta: isize) {
         match self.options_column {
             OptionsColumn::Provider => {
-                let providers = [Provider::OpenAI, Provider::Anthropic];
-                let len = providers.len() as isize;
-                if let Some(idx) = providers
-                    .iter()
-                    .position(|&provider| provider == self.selected_provider)
-                {
+                let scopes = self.scopes();
+                let len = scopes.len() as isize;
+                if let Some(idx) = scopes.iter().position(|&scope| scope == self.scope()) {
                     let next = (idx as isize + delta).rem_euclid(len);
-                    let new_provider = providers[next as usize];
-                    if new_provider != self.selected_provider {
-                        self.selected_provider = new_provider;
-                        self.reset_filter();
-                        if !self.has_client(new_provider) {
-                            self.show_api_key_popup(new_provider);
-                        } else {
+                    match scopes[next as usize] {
+                        Scope::Provider(new_provider) => {
+                            let was_aggregate = self.aggregate.take().is_some();
+                            if was_aggregate {
+                                self.group_by = GroupBy::Model;
+                            }
+                            if was_aggregate || new_provider != self.selected_provider {
+                                self.selected_provider = new_provider;
+                                self.reset_filter();
+                                if !self.has_client(new_provider) {
+                                    self.show_api_key_popup(new_provider);
+                                } else {
+                                    self.cancel_api_key_popup();
+                                }
+                            }
+                        }
+                        aggregate => {
+                            self.aggregate = Some(aggregate);
+                            self.reset_filter();
                             self.cancel_api_key_popup();
+                            self.group_by = GroupBy::Provider;
                         }
                     }
                 }
             }
             OptionsColumn::Metric => {
-                let metrics = [View::Usage, View::Cost];
+                let metrics = [View::Usage, View::Cost, View::Live, View::Reconcile];
                 let len = metrics.len() as isize;
                 if let Some(idx) = metrics.iter().position(|&view| view == self.current_view) {
                     let next = (idx as isize + delta).rem_euclid(len);
                     let new_view = metrics[next as usize];
                     if new_view != self.current_view {
                         self.current_view = new_view;
-                        if self.current_view == View::Cost {
-                            self.group_by = GroupBy::Model;
+                        if self.current_view != View::Usage {
+                            if self.group_by == GroupBy::ApiKeys {
+                                self.group_by = GroupBy::Model;
+                            }
                             self.reset_filter();
                         }
                     }
@@ -159,8 +426,8 @@ impl App {
                     } else {
                         self.selected_filter = filters.get(self.filter_cursor_index - 1).cloned();
                     }
-                } else if self.current_view == View::Usage {
-                    let group_by_options = [GroupBy::Model, GroupBy::ApiKeys];
+                } else {
+                    let group_by_options = self.group_by_options();
                     let len = group_by_options.len() as isize;
                     if let Some(idx) = group_by_options
                         .iter()
@@ -177,7 +444,7 @@ impl App {
                 }
             }
             OptionsColumn::Range => {
-                let ranges = [Range::SevenDays, Range::ThirtyDays];
+                let ranges = Range::ALL;
                 let len = ranges.len() as isize;
                 if let Some(idx) = ranges.iter().position(|&r| r == self.range) {
                     let next = (idx as isize + delta).rem_euclid(len);
@@ -188,20 +455,21 @@ impl App {
     }
 
     pub fn scroll_chart(&mut self, delta: isize) {
-        let provider = self.current_provider();
+        let scope = self.scope();
         let current_view = self.current_view;
-        let info = self.provider_info_mut(provider);
-        let (scroll_value, data_len) = match current_view {
-            View::Cost => (&mut info.cost_chart_scroll, info.cost_data.len()),
-            View::Usage => (&mut info.usage_chart_scroll, info.usage_data.len()),
+        let data_len = match current_view {
+            View::Cost => self.cost_data(scope).len(),
+            View::Usage => self.usage_data(scope).len(),
+            View::Live | View::Reconcile => return,
         };
+        let scroll_value = self.chart_scroll_mut(scope, current_view);
 
         if delta == 0 || data_len == 0 {
             return;
         }
 
         if delta < 0 {
-            let amount = delta.unsigned_abs() as usize;
+            let amount = delta.unsigned_abs();
             *scroll_value = scroll_value.saturating_sub(amount);
         } else {
             let amount = delta as usize;
@@ -210,74 +478,574 @@ impl App {
         }
     }
 
-    pub fn set_openai_client(&mut self, api_key: String) {
-        let info = self.providers.get_mut(&Provider::OpenAI).unwrap();
-        info.client = Some(ProviderClient::OpenAI(OpenAIClient::new(api_key)));
+    pub fn set_api_key(&mut self, provider: ProviderId, api_key: String) {
+        let info = self.provider_info_mut(provider);
+        info.key_hash = Some(cache::key_hash(&api_key));
+        info.provider = info.provider.connect(api_key);
         info.initial_fetch_done = false;
+        // A new key may belong to a different organization, so start over
+        // with a full fetch rather than merging into the old data.
+        info.cost_data.clear();
+        info.usage_data.clear();
+        info.cost_buckets.clear();
+        info.usage_buckets.clear();
+        info.hourly_usage_data.clear();
+        info.hourly_usage_buckets.clear();
+        info.hourly_updated = None;
+        info.live_usage_data.clear();
+        info.live_updated = None;
+        info.api_key_names.clear();
+        info.last_updated = None;
+        info.covered_from = None;
         self.ensure_selection_has_client();
     }
 
-    pub fn set_anthropic_client(&mut self, api_key: String) {
-        let info = self.providers.get_mut(&Provider::Anthropic).unwrap();
-        info.client = Some(ProviderClient::Anthropic(AnthropicClient::new(api_key)));
-        info.initial_fetch_done = false;
-        self.ensure_selection_has_client();
+    pub fn provider_ids(&self) -> Vec<ProviderId> {
+        (0..self.providers.len()).map(ProviderId).collect()
     }
 
-    pub fn current_provider(&self) -> Provider {
+    pub fn current_provider(&self) -> ProviderId {
         self.selected_provider
     }
 
+    pub fn scope(&self) -> Scope {
+        self.aggregate
+            .unwrap_or(Scope::Provider(self.selected_provider))
+    }
+
+    /// Entries of the Providers column, in order: each account, followed by
+    /// an aggregate entry for providers with more than one, then "All".
+    pub fn scopes(&self) -> Vec<Scope> {
+        let mut scopes = Vec::new();
+        for (idx, info) in self.providers.iter().enumerate() {
+            scopes.push(Scope::Provider(ProviderId(idx)));
+            let kind = info.provider.id();
+            let last_of_kind = self
+                .providers
+                .get(idx + 1)
+                .is_none_or(|next| next.provider.id() != kind);
+            let first_of_kind = idx == 0 || self.providers[idx - 1].provider.id() != kind;
+            if last_of_kind && !first_of_kind {
+                scopes.push(Scope::Accounts(kind));
+            }
+        }
+        scopes.push(Scope::All);
+        scopes
+    }
+
+    /// Group By choices for the current scope and view.
+    pub fn group_by_options(&self) -> &'static [GroupBy] {
+        if self.aggregate.is_some() {
+            &[GroupBy::Provider, GroupBy::ProviderModel]
+        } else if self.current_view == View::Usage {
+            &[GroupBy::Model, GroupBy::ApiKeys]
+        } else {
+            &[GroupBy::Model]
+        }
+    }
+
     pub fn ensure_selection_has_client(&mut self) {
         if !self.has_client(self.selected_provider) {
-            if self.has_client(Provider::OpenAI) {
-                self.selected_provider = Provider::OpenAI;
-            } else if self.has_client(Provider::Anthropic) {
-                self.selected_provider = Provider::Anthropic;
+            if let Some(id) = self
+                .provider_ids()
+                .into_iter()
+                .find(|&id| self.has_client(id))
+            {
+                self.selected_provider = id;
             }
         }
     }
 
-    pub fn provider_info(&self, provider: Provider) -> &ProviderInfo {
-        self.providers.get(&provider).unwrap()
+    pub fn provider(&self, provider: ProviderId) -> &dyn UsageProvider {
+        self.provider_info(provider).provider.as_ref()
     }
 
-    pub fn provider_info_mut(&mut self, provider: Provider) -> &mut ProviderInfo {
-        self.providers.get_mut(&provider).unwrap()
+    pub fn provider_info(&self, provider: ProviderId) -> &ProviderInfo {
+        &self.providers[provider.0]
     }
 
-    pub fn has_client(&self, provider: Provider) -> bool {
-        self.provider_info(provider).client.is_some()
+    pub fn provider_info_mut(&mut self, provider: ProviderId) -> &mut ProviderInfo {
+        &mut self.providers[provider.0]
     }
 
-    pub fn initial_fetch_done(&self, provider: Provider) -> bool {
+    pub fn has_client(&self, provider: ProviderId) -> bool {
+        self.provider(provider).is_connected()
+    }
+
+    pub fn initial_fetch_done(&self, provider: ProviderId) -> bool {
         self.provider_info(provider).initial_fetch_done
     }
 
-    pub fn mark_initial_fetch_done(&mut self, provider: Provider) {
+    pub fn mark_initial_fetch_done(&mut self, provider: ProviderId) {
         self.provider_info_mut(provider).initial_fetch_done = true;
     }
 
-    pub fn error_for_provider(&self, provider: Provider, view: View) -> Option<&String> {
+    pub fn has_data(&self, provider: ProviderId) -> bool {
+        let info = self.provider_info(provider);
+        !info.cost_data.is_empty() || !info.usage_data.is_empty()
+    }
+
+    pub fn error_for_provider(&self, provider: ProviderId, view: View) -> Option<&String> {
         let info = self.provider_info(provider);
         match view {
             View::Cost => info.errors.cost.as_ref(),
-            View::Usage => info.errors.usage.as_ref(),
+            View::Usage | View::Live => info.errors.usage.as_ref(),
+            View::Reconcile => info.errors.cost.as_ref().or(info.errors.usage.as_ref()),
         }
     }
 
-    pub fn data_for_provider(&self, provider: Provider) -> Option<&[DailyData]> {
-        Some(&self.provider_info(provider).cost_data)
+    /// Usage at the granularity of the selected range.
+    fn usage_data_for_provider(&self, provider: ProviderId) -> &[DailyUsageData] {
+        let info = self.provider_info(provider);
+        match self.range.bucket_width() {
+            BucketWidth::Day => &info.usage_data,
+            BucketWidth::Hour | BucketWidth::Minute => &info.hourly_usage_data,
+        }
     }
 
-    pub fn usage_data_for_provider(
-        &self,
-        provider: Provider,
-    ) -> Option<&[crate::models::DailyUsageData]> {
-        Some(&self.provider_info(provider).usage_data)
+    fn usage_buckets_for_provider(&self, provider: ProviderId) -> &[DateTime<Utc>] {
+        let info = self.provider_info(provider);
+        match self.range.bucket_width() {
+            BucketWidth::Day => &info.usage_buckets,
+            BucketWidth::Hour | BucketWidth::Minute => &info.hourly_usage_buckets,
+        }
+    }
+
+    /// Accounts whose data `scope` covers.
+    pub fn scope_providers(&self, scope: Scope) -> Vec<ProviderId> {
+        let connected = |id: &ProviderId| self.has_client(*id);
+        match scope {
+            Scope::Provider(provider) => vec![provider],
+            Scope::Accounts(kind) => self
+                .provider_ids()
+                .into_iter()
+                .filter(|&id| self.provider(id).id() == kind)
+                .filter(connected)
+                .collect(),
+            Scope::All => self.provider_ids().into_iter().filter(connected).collect(),
+        }
+    }
+
+    /// Label of the account `provider`, e.g. "OpenAI" or "OpenAI/prod".
+    pub fn account_label(&self, provider: ProviderId) -> String {
+        self.provider_info(provider).label()
+    }
+
+    pub fn scope_label(&self, scope: Scope) -> String {
+        match scope {
+            Scope::Provider(provider) => self.account_label(provider),
+            Scope::Accounts(kind) => {
+                let label = self
+                    .providers
+                    .iter()
+                    .find(|info| info.provider.id() == kind)
+                    .map_or(kind, |info| info.provider.label());
+                format!("{}/all", label)
+            }
+            Scope::All => "All providers".to_string(),
+        }
+    }
+
+    pub fn scope_palette(&self, scope: Scope) -> ColorPalette {
+        match scope {
+            Scope::Provider(provider) => self.provider(provider).palette(),
+            Scope::Accounts(kind) => self
+                .providers
+                .iter()
+                .find(|info| info.provider.id() == kind)
+                .map_or_else(ColorPalette::combined, |info| info.provider.palette()),
+            Scope::All => ColorPalette::combined(),
+        }
+    }
+
+    pub fn scope_has_client(&self, scope: Scope) -> bool {
+        match scope {
+            Scope::Provider(provider) => self.has_client(provider),
+            Scope::Accounts(_) | Scope::All => !self.scope_providers(scope).is_empty(),
+        }
+    }
+
+    pub fn scope_has_data(&self, scope: Scope) -> bool {
+        self.scope_providers(scope)
+            .into_iter()
+            .any(|id| self.has_data(id))
+    }
+
+    pub fn scope_is_loading(&self, scope: Scope) -> bool {
+        self.scope_providers(scope)
+            .into_iter()
+            .any(|id| self.is_loading(id))
+    }
+
+    /// The first error among the providers in `scope`. Combined totals
+    /// would be silently short otherwise, so one failure fails the view.
+    pub fn scope_error(&self, scope: Scope, view: View) -> Option<String> {
+        self.scope_providers(scope).into_iter().find_map(|id| {
+            let err = self.error_for_provider(id, view)?;
+            Some(match scope {
+                Scope::Provider(_) => err.clone(),
+                Scope::Accounts(_) | Scope::All => {
+                    format!("{}: {}", self.account_label(id), err)
+                }
+            })
+        })
+    }
+
+    /// Series key of a row in the combined view.
+    fn combined_key(&self, provider: ProviderId, model: &Option<String>) -> String {
+        let label = self.account_label(provider);
+        match self.group_by {
+            GroupBy::Provider => label,
+            _ => format!(
+                "{} / {}",
+                label,
+                shared::extract_trimmed_string(model).unwrap_or("unknown")
+            ),
+        }
+    }
+
+    /// Cost for `scope`. Combined rows get their provider (or provider and
+    /// model) as the line item, so the charts and filters stack by it
+    /// without knowing about providers.
+    pub fn cost_data(&self, scope: Scope) -> Cow<'_, [DailyData]> {
+        match scope {
+            Scope::Provider(provider) => Cow::Borrowed(&self.provider_info(provider).cost_data),
+            Scope::Accounts(_) | Scope::All => Cow::Owned(
+                self.scope_providers(scope)
+                    .into_iter()
+                    .flat_map(|id| {
+                        self.provider_info(id)
+                            .cost_data
+                            .iter()
+                            .map(move |d| DailyData {
+                                line_item: Some(self.combined_key(id, &d.line_item)),
+                                ..d.clone()
+                            })
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Usage for `scope` at the granularity of the selected range. Combined
+    /// rows carry their series key in `model`, like `cost_data`.
+    pub fn usage_data(&self, scope: Scope) -> Cow<'_, [DailyUsageData]> {
+        match scope {
+            Scope::Provider(provider) => Cow::Borrowed(self.usage_data_for_provider(provider)),
+            Scope::Accounts(_) | Scope::All => Cow::Owned(
+                self.scope_providers(scope)
+                    .into_iter()
+                    .flat_map(|id| {
+                        self.usage_data_for_provider(id)
+                            .iter()
+                            .map(move |d| DailyUsageData {
+                                model: Some(self.combined_key(id, &d.model)),
+                                api_key_id: None,
+                                ..d.clone()
+                            })
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn cost_buckets(&self, scope: Scope) -> Cow<'_, [DateTime<Utc>]> {
+        self.scope_buckets(scope, |id| &self.provider_info(id).cost_buckets)
+    }
+
+    pub fn usage_buckets(&self, scope: Scope) -> Cow<'_, [DateTime<Utc>]> {
+        self.scope_buckets(scope, |id| self.usage_buckets_for_provider(id))
+    }
+
+    /// Buckets every provider in `scope` returned. A combined total is only
+    /// complete where none of them is missing.
+    fn scope_buckets<'a>(
+        &'a self,
+        scope: Scope,
+        buckets: impl Fn(ProviderId) -> &'a [DateTime<Utc>],
+    ) -> Cow<'a, [DateTime<Utc>]> {
+        match scope {
+            Scope::Provider(provider) => Cow::Borrowed(buckets(provider)),
+            Scope::Accounts(_) | Scope::All => {
+                let providers = self.scope_providers(scope);
+                let Some((first, rest)) = providers.split_first() else {
+                    return Cow::Owned(Vec::new());
+                };
+                Cow::Owned(
+                    buckets(*first)
+                        .iter()
+                        .filter(|date| rest.iter().all(|&id| buckets(id).contains(date)))
+                        .copied()
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// When the data behind the selected range was last refreshed. For the
+    /// combined view that's the provider refreshed longest ago.
+    pub fn last_updated(&self, scope: Scope) -> Option<DateTime<Utc>> {
+        let updated = |id: ProviderId| {
+            let info = self.provider_info(id);
+            match self.range.bucket_width() {
+                BucketWidth::Day => info.last_updated,
+                BucketWidth::Hour | BucketWidth::Minute => info.hourly_updated,
+            }
+        };
+        let providers = self.scope_providers(scope);
+        if providers.iter().any(|&id| updated(id).is_none()) {
+            return None;
+        }
+        providers.into_iter().filter_map(updated).min()
+    }
+
+    /// Requests retried during the last fetch of every provider in `scope`.
+    pub fn scope_retries(&self, scope: Scope) -> u32 {
+        self.scope_providers(scope)
+            .into_iter()
+            .map(|id| self.provider_info(id).retries)
+            .sum()
+    }
+
+    /// Warnings from the last fetch of every provider in `scope`, labelled
+    /// by account in aggregates.
+    pub fn scope_warnings(&self, scope: Scope) -> Vec<String> {
+        self.scope_providers(scope)
+            .into_iter()
+            .flat_map(|id| {
+                self.provider_info(id)
+                    .warnings
+                    .iter()
+                    .map(move |warning| match scope {
+                        Scope::Provider(_) => warning.clone(),
+                        Scope::Accounts(_) | Scope::All => {
+                            format!("{}: {}", self.account_label(id), warning)
+                        }
+                    })
+            })
+            .collect()
+    }
+
+    /// Endpoint progress of each account in `scope` that is fetching.
+    pub fn scope_progress(&self, scope: Scope) -> Vec<(ProviderId, Steps)> {
+        self.scope_providers(scope)
+            .into_iter()
+            .filter(|&id| self.is_loading(id))
+            .map(|id| (id, self.provider_info(id).provider.progress().snapshot()))
+            .collect()
+    }
+
+    /// Budgets covering any account in `scope` (any connected account for
+    /// `None`) and how much of each is spent, counted over every connected
+    /// account the budget covers.
+    pub fn budget_statuses(&self, scope: Option<Scope>) -> Vec<BudgetStatus<'_>> {
+        let connected: Vec<&ProviderInfo> = self
+            .provider_ids()
+            .into_iter()
+            .filter(|&id| self.has_client(id))
+            .map(|id| self.provider_info(id))
+            .collect();
+        let in_scope: Vec<&ProviderInfo> = match scope {
+            Some(scope) => self
+                .scope_providers(scope)
+                .into_iter()
+                .map(|id| self.provider_info(id))
+                .collect(),
+            None => connected.clone(),
+        };
+        let today = Utc::now().date_naive();
+        self.budgets
+            .iter()
+            .filter(|budget| {
+                connected.iter().any(|info| budget.covers(info))
+                    && in_scope.iter().any(|info| budget.covers(info))
+            })
+            .map(|budget| BudgetStatus {
+                budget,
+                spent: budget.spent(&connected, today),
+            })
+            .collect()
+    }
+
+    /// Budgets at or past their warning threshold, most used first.
+    pub fn budget_alerts(&self) -> Vec<BudgetStatus<'_>> {
+        let mut alerts: Vec<_> = self
+            .budget_statuses(None)
+            .into_iter()
+            .filter(|status| status.warning())
+            .collect();
+        alerts.sort_by(|a, b| b.percent().total_cmp(&a.percent()));
+        alerts
+    }
+
+    /// The lowest daily cost limit on exactly the accounts in `scope`, with
+    /// no model or key filter, so each day's bar can be held against it.
+    pub fn daily_cost_limit(&self, scope: Scope) -> Option<f64> {
+        let accounts = self.scope_providers(scope);
+        self.budgets
+            .iter()
+            .filter(|budget| {
+                budget.period == Period::Daily
+                    && budget.tokens.is_none()
+                    && budget.model.is_none()
+                    && budget.api_key.is_none()
+            })
+            .filter(|budget| {
+                self.provider_ids()
+                    .into_iter()
+                    .filter(|&id| self.has_client(id) && budget.covers(self.provider_info(id)))
+                    .eq(accounts.iter().copied())
+            })
+            .map(|budget| budget.limit())
+            .reduce(f64::min)
+    }
+
+    /// Token-derived cost of the usage in `scope` over the selected range,
+    /// keyed like the usage chart's series. Priced by each row's own model,
+    /// so combined and per-key series work too.
+    pub fn estimated_costs(&self, scope: Scope) -> CostEstimate {
+        let window = self.range_window();
+        let mut estimate = CostEstimate::default();
+        for id in self.scope_providers(scope) {
+            let rows = self.usage_data_for_provider(id);
+            for d in rows.iter().filter(|d| window.contains(d.date)) {
+                let series = match (scope, self.group_by) {
+                    (Scope::Provider(_), GroupBy::ApiKeys) => {
+                        shared::extract_trimmed_string(&d.api_key_id)
+                            .unwrap_or("unknown")
+                            .to_string()
+                    }
+                    (Scope::Provider(_), _) => shared::extract_trimmed_string(&d.model)
+                        .unwrap_or("unknown")
+                        .to_string(),
+                    (Scope::Accounts(_) | Scope::All, _) => self.combined_key(id, &d.model),
+                };
+                match self.pricing.estimate(d) {
+                    Some(cost) => *estimate.by_series.entry(series).or_insert(0.0) += cost,
+                    None => {
+                        *estimate.unpriced_tokens.entry(series).or_insert(0) +=
+                            d.input_tokens + d.output_tokens
+                    }
+                }
+            }
+        }
+        estimate
     }
 
-    pub fn show_api_key_popup(&mut self, provider: Provider) {
+    /// Billed cost next to token-derived estimates for each day and model of
+    /// every account in `scope` over the selected range.
+    pub fn reconciliation(&self, scope: Scope) -> Vec<Reconciliation> {
+        let window = self.range_window();
+        if window.width != BucketWidth::Day {
+            return Vec::new();
+        }
+        self.scope_providers(scope)
+            .into_iter()
+            .flat_map(|id| {
+                let info = self.provider_info(id);
+                reconcile::reconcile(
+                    &self.account_label(id),
+                    &info.cost_data,
+                    &info.usage_data,
+                    &self.pricing,
+                    window,
+                )
+            })
+            .collect()
+    }
+
+    /// Spikes in the series the current view charts over the selected range:
+    /// cost per line item, or tokens per model or API key. Narrowed to the
+    /// selected filter like the chart.
+    pub fn anomalies(&self, scope: Scope) -> Vec<Anomaly> {
+        let window = self.range_window();
+        let mut values: HashMap<String, HashMap<DateTime<Utc>, f64>> = HashMap::new();
+        let buckets: Vec<DateTime<Utc>> = match self.current_view {
+            View::Cost if window.width == BucketWidth::Day => {
+                for d in self.cost_data(scope).iter().filter(|d| window.contains(d.date)) {
+                    let series = shared::extract_trimmed_string(&d.line_item).unwrap_or("unknown");
+                    *values
+                        .entry(series.to_string())
+                        .or_default()
+                        .entry(d.date)
+                        .or_insert(0.0) += d.cost;
+                }
+                self.cost_buckets(scope).to_vec()
+            }
+            View::Usage => {
+                for d in self.usage_data(scope).iter().filter(|d| window.contains(d.date)) {
+                    let series = match self.group_by {
+                        GroupBy::ApiKeys => shared::extract_trimmed_string(&d.api_key_id),
+                        GroupBy::Model | GroupBy::Provider | GroupBy::ProviderModel => {
+                            shared::extract_trimmed_string(&d.model)
+                        }
+                    };
+                    *values
+                        .entry(series.unwrap_or("unknown").to_string())
+                        .or_default()
+                        .entry(d.date)
+                        .or_insert(0.0) += (d.input_tokens + d.output_tokens) as f64;
+                }
+                self.usage_buckets(scope).to_vec()
+            }
+            View::Cost | View::Live | View::Reconcile => return Vec::new(),
+        };
+        if let Some(filter) = &self.selected_filter {
+            values.retain(|series, _| series == filter);
+        }
+        let buckets: Vec<DateTime<Utc>> = buckets
+            .into_iter()
+            .filter(|date| window.contains(*date))
+            .collect();
+        anomaly::detect(&values, &buckets)
+    }
+
+    /// Month-end cost forecast for `scope`, narrowed to one line item when
+    /// `item` is set.
+    pub fn cost_forecast(&self, scope: Scope, item: Option<&str>) -> Option<Forecast> {
+        let today = self.forecast_day()?;
+        let daily = forecast::daily_costs(&self.cost_data(scope), &self.cost_buckets(scope), item);
+        Forecast::new(&daily, today)
+    }
+
+    /// Month-end cost forecast of each of `items` in `scope` that has enough
+    /// history for one.
+    pub fn item_cost_forecasts(&self, scope: Scope, items: &[String]) -> HashMap<String, Forecast> {
+        let Some(today) = self.forecast_day() else {
+            return HashMap::new();
+        };
+        let (data, buckets) = (self.cost_data(scope), self.cost_buckets(scope));
+        items
+            .iter()
+            .filter_map(|item| {
+                let daily = forecast::daily_costs(&data, &buckets, Some(item));
+                Some((item.clone(), Forecast::new(&daily, today)?))
+            })
+            .collect()
+    }
+
+    /// Today, if the selected range ends with it. Other ranges have no
+    /// month left to project.
+    fn forecast_day(&self) -> Option<NaiveDate> {
+        let window = self.range_window();
+        let now = Utc::now();
+        (window.width == BucketWidth::Day && window.contains(now)).then(|| now.date_naive())
+    }
+
+    pub fn chart_scroll_mut(&mut self, scope: Scope, view: View) -> &mut usize {
+        match (scope, view) {
+            (Scope::Provider(provider), View::Cost) => {
+                &mut self.provider_info_mut(provider).cost_chart_scroll
+            }
+            (Scope::Provider(provider), _) => {
+                &mut self.provider_info_mut(provider).usage_chart_scroll
+            }
+            (_, View::Cost) => &mut self.combined_cost_chart_scroll,
+            (_, _) => &mut self.combined_usage_chart_scroll,
+        }
+    }
+
+    pub fn show_api_key_popup(&mut self, provider: ProviderId) {
         self.api_key_popup_active = Some(provider);
         self.api_key_input.clear();
     }
@@ -291,10 +1059,7 @@ impl App {
         if let Some(provider) = self.api_key_popup_active {
             let key = self.api_key_input.trim().to_string();
             if !key.is_empty() {
-                match provider {
-                    Provider::OpenAI => self.set_openai_client(key),
-                    Provider::Anthropic => self.set_anthropic_client(key),
-                }
+                self.set_api_key(provider, key);
                 self.api_key_popup_active = None;
                 self.api_key_input.clear();
                 return true;
@@ -313,52 +1078,441 @@ impl App {
         }
     }
 
-    pub fn update_animation_frame(&mut self) {
-        let provider = self.current_provider();
+    pub fn open_custom_range_popup(&mut self) {
+        self.custom_range_input = Some(CustomRangeInput {
+            start: self.custom_range.start.format("%Y-%m-%d").to_string(),
+            end: self.custom_range.end.format("%Y-%m-%d").to_string(),
+            editing_end: false,
+            error: None,
+        });
+    }
+
+    pub fn cancel_custom_range_popup(&mut self) {
+        self.custom_range_input = None;
+    }
+
+    pub fn handle_custom_range_input(&mut self, key_code: KeyCode) {
+        if let Some(input) = self.custom_range_input.as_mut() {
+            let field = if input.editing_end {
+                &mut input.end
+            } else {
+                &mut input.start
+            };
+            match key_code {
+                KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                    input.editing_end = !input.editing_end;
+                }
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '-' => field.push(c),
+                KeyCode::Backspace => {
+                    field.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Applies the dates typed into the custom range popup. Returns false and
+    /// keeps the popup open if they don't form a valid range.
+    pub fn submit_custom_range(&mut self) -> bool {
+        let Some(input) = self.custom_range_input.as_mut() else {
+            return false;
+        };
+        let parse = |s: &str| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d");
+        match (parse(&input.start), parse(&input.end)) {
+            (Ok(start), Ok(end))
+                if start <= end
+                    && end <= Utc::now().date_naive()
+                    && (end - start).num_days() < MAX_CUSTOM_RANGE_DAYS =>
+            {
+                self.custom_range = DateWindow { start, end };
+                self.range = Range::Custom;
+                self.custom_range_input = None;
+                true
+            }
+            (Ok(start), Ok(end)) if start > end => {
+                input.error = Some("Start date must be before end date".to_string());
+                false
+            }
+            (Ok(start), Ok(end)) if (end - start).num_days() >= MAX_CUSTOM_RANGE_DAYS => {
+                input.error = Some(format!(
+                    "Custom ranges can span at most {} days",
+                    MAX_CUSTOM_RANGE_DAYS
+                ));
+                false
+            }
+            (Ok(_), Ok(_)) => {
+                input.error = Some("End date can't be in the future".to_string());
+                false
+            }
+            _ => {
+                input.error = Some("Dates must be in YYYY-MM-DD format".to_string());
+                false
+            }
+        }
+    }
+
+    /// Buckets covered by the selected range. Rolling ranges always end with
+    /// the current day or hour (UTC), whether or not there is data for it.
+    pub fn range_window(&self) -> BucketWindow {
+        match self.range.rolling_hours() {
+            Some(hours) => BucketWindow::ending_now(BucketWidth::Hour, hours),
+            None => BucketWindow::from_dates(self.date_window()),
+        }
+    }
+
+    /// Calendar days covered by the selected range.
+    pub fn date_window(&self) -> DateWindow {
+        let today = Utc::now().date_naive();
+        if let Some(days) = self.range.rolling_days() {
+            return DateWindow::ending(today, days);
+        }
+        match self.range {
+            Range::Last24Hours | Range::Last72Hours => {
+                let window = self.range_window();
+                DateWindow {
+                    start: window.start.date_naive(),
+                    end: today,
+                }
+            }
+            Range::MonthToDate => DateWindow::month_to_date(today),
+            Range::PreviousMonth => DateWindow::previous_month(today),
+            Range::Custom => self.custom_range,
+            Range::SevenDays | Range::ThirtyDays | Range::NinetyDays => {
+                unreachable!("rolling ranges are handled by rolling_days")
+            }
+        }
+    }
+
+    /// Earliest day the selected range needs data for, including the
+    /// previous period used for the week-over-week comparison.
+    pub fn required_fetch_start(&self) -> DateTime<Utc> {
+        let window = self.date_window();
+        let start = if self.range == Range::SevenDays {
+            window.previous().start_time()
+        } else {
+            window.start_time()
+        };
+        start.min(fetch::default_start_time())
+    }
+
+    /// Whether the selected range reaches further back than the data
+    /// fetched for `provider` so far, or for hourly ranges, whether the
+    /// current hour is missing.
+    pub fn needs_fetch_for_range(&self, provider: ProviderId) -> bool {
         let info = self.provider_info(provider);
-        let has_data = !info.cost_data.is_empty() || !info.usage_data.is_empty();
-        if self.loading || !has_data {
+        if !self.has_client(provider) || !info.initial_fetch_done {
+            return false;
+        }
+        match self.range.bucket_width() {
+            BucketWidth::Day => info
+                .covered_from
+                .is_none_or(|from| from > self.required_fetch_start()),
+            BucketWidth::Hour | BucketWidth::Minute => info
+                .hourly_updated
+                .is_none_or(|updated| updated < BucketWidth::Hour.truncate(Utc::now())),
+        }
+    }
+
+    pub fn is_loading(&self, provider: ProviderId) -> bool {
+        self.provider_info(provider).fetch.is_some()
+    }
+
+    pub fn any_loading(&self) -> bool {
+        self.providers.iter().any(|info| info.fetch.is_some())
+    }
+
+    /// Whether any provider is missing data for the selected range.
+    pub fn range_needs_fetch(&self) -> bool {
+        self.provider_ids()
+            .into_iter()
+            .any(|id| self.needs_fetch_for_range(id))
+    }
+
+    pub fn update_animation_frame(&mut self) {
+        let scope = self.scope();
+        if self.scope_is_loading(scope) || !self.scope_has_data(scope) {
             self.animation_frame = self.animation_frame.wrapping_add(1);
         } else {
             self.animation_frame = 0;
         }
     }
 
-    pub fn start_fetch(&mut self) {
-        self.loading = true;
-        let provider = self.current_provider();
+    /// Marks a fetch for `provider` as running, superseding one that already
+    /// is, and returns the generation to finish it with. The task is
+    /// attached with `track_fetch` once spawned.
+    pub fn start_fetch(&mut self, provider: ProviderId) -> u64 {
+        self.fetch_generation += 1;
+        let generation = self.fetch_generation;
         let info = self.provider_info_mut(provider);
+        let previous_errors = match info.fetch.take() {
+            Some(stale) => {
+                if let Some(handle) = stale.handle {
+                    handle.abort();
+                }
+                stale.previous_errors
+            }
+            None => std::mem::take(&mut info.errors),
+        };
         info.errors = crate::provider::ProviderErrors::default();
+        info.provider.progress().reset();
+        info.fetch = Some(InFlightFetch {
+            generation,
+            handle: None,
+            previous_errors,
+        });
+        generation
+    }
+
+    pub fn track_fetch(&mut self, provider: ProviderId, handle: AbortHandle) {
+        if let Some(fetch) = self.provider_info_mut(provider).fetch.as_mut() {
+            fetch.handle = Some(handle);
+        }
+    }
+
+    /// Aborts every running fetch. Nothing is applied until a fetch
+    /// finishes, so the data stays as it was; only the errors it cleared
+    /// are put back.
+    pub fn cancel_fetches(&mut self) {
+        let mut cancelled = false;
+        for info in &mut self.providers {
+            if let Some(fetch) = info.fetch.take() {
+                if let Some(handle) = fetch.handle {
+                    handle.abort();
+                }
+                info.errors = fetch.previous_errors;
+                cancelled = true;
+            }
+        }
+        // Otherwise a refresh the timer started would be due again at once.
+        if let (true, Some(auto_refresh)) = (cancelled, self.auto_refresh.as_mut()) {
+            auto_refresh.next_at = Some(Utc::now() + auto_refresh.delay());
+        }
+    }
+
+    /// Applies the outcome of the fetch started as `generation`, unless it
+    /// was cancelled or superseded in the meantime.
+    pub fn finish_fetch(&mut self, outcome: crate::provider::FetchOutcome, generation: u64) {
+        let provider = outcome.provider;
+        let current = self.provider_info(provider).fetch.as_ref();
+        if current.is_none_or(|fetch| fetch.generation != generation) {
+            return;
+        }
+        let fetched_at = outcome.fetched_at;
+        let live_poll = outcome.width == BucketWidth::Minute;
+        self.apply_outcome(outcome);
+        self.provider_info_mut(provider).fetch = None;
+
+        // A refresh covers every provider, so it only counts as finished (or
+        // failed) once the last one has reported back. Live polls run on
+        // their own schedule and leave the refresh timer alone.
+        if live_poll || self.any_loading() {
+            return;
+        }
+        let failed = self
+            .providers
+            .iter()
+            .any(|info| info.errors.cost.is_some() || info.errors.usage.is_some());
+        self.last_refresh = Some(fetched_at);
+        if let Some(auto_refresh) = self.auto_refresh.as_mut() {
+            auto_refresh.schedule(fetched_at, failed);
+        }
     }
 
-    pub fn finish_fetch(&mut self, outcome: crate::provider::FetchOutcome) {
+    fn apply_outcome(&mut self, outcome: crate::provider::FetchOutcome) {
         let info = self.provider_info_mut(outcome.provider);
-        info.cost_data = outcome.cost_data;
-        info.usage_data = outcome.usage_data;
-        info.api_key_names = outcome.api_key_names;
+        if outcome.width == BucketWidth::Minute {
+            merge_usage_errors(info, outcome.errors.usage, outcome.warnings);
+            if let Some(usage) = outcome.usage_data {
+                info.live_usage_data = usage.data;
+                if info.errors.usage.is_none() {
+                    info.live_updated = Some(outcome.fetched_at);
+                }
+            }
+            return;
+        }
+        if outcome.width == BucketWidth::Hour {
+            if let Some(usage) = outcome.usage_data {
+                info.hourly_usage_data = usage.data;
+                info.hourly_usage_buckets = usage.bucket_dates;
+                if outcome.errors.usage.is_none() {
+                    info.hourly_updated = Some(outcome.fetched_at);
+                }
+            }
+            info.api_key_names.extend(outcome.api_key_names);
+            merge_usage_errors(info, outcome.errors.usage, outcome.warnings);
+            return;
+        }
+        if let Some(cost) = outcome.cost_data {
+            fetch::merge_since(&mut info.cost_data, cost.data, outcome.start_time, |d| {
+                d.date
+            });
+            fetch::merge_since(
+                &mut info.cost_buckets,
+                cost.bucket_dates,
+                outcome.start_time,
+                |d| *d,
+            );
+        }
+        if let Some(usage) = outcome.usage_data {
+            fetch::merge_since(&mut info.usage_data, usage.data, outcome.start_time, |d| {
+                d.date
+            });
+            fetch::merge_since(
+                &mut info.usage_buckets,
+                usage.bucket_dates,
+                outcome.start_time,
+                |d| *d,
+            );
+        }
+        info.api_key_names.extend(outcome.api_key_names);
+        // Partial failures and truncated reports leave gaps, so only a clean
+        // fetch moves the incremental window forward.
+        if outcome.errors.cost.is_none()
+            && outcome.errors.usage.is_none()
+            && outcome.warnings.is_empty()
+        {
+            info.last_updated = Some(outcome.fetched_at);
+            info.covered_from = Some(
+                info.covered_from
+                    .map_or(outcome.start_time, |from| from.min(outcome.start_time)),
+            );
+        }
         info.errors = outcome.errors;
+        info.retries = outcome.retries;
+        info.warnings = outcome.warnings;
         self.mark_initial_fetch_done(outcome.provider);
-        self.loading = false;
     }
 
-    pub fn get_clients(&self) -> (Option<OpenAIClient>, Option<AnthropicClient>) {
-        let openai_client = self
-            .provider_info(Provider::OpenAI)
-            .client
-            .as_ref()
-            .and_then(|c| match c {
-                ProviderClient::OpenAI(client) => Some(client.clone()),
-                _ => None,
-            });
-        let anthropic_client = self
-            .provider_info(Provider::Anthropic)
-            .client
+    /// Shows data from a previous session until the first fetch completes.
+    pub fn restore_cache(&mut self, provider: ProviderId, entry: CacheEntry) {
+        let info = self.provider_info_mut(provider);
+        info.cost_data = entry.cost_data;
+        info.usage_data = entry.usage_data;
+        info.cost_buckets = entry.cost_buckets;
+        info.usage_buckets = entry.usage_buckets;
+        info.api_key_names = entry.api_key_names;
+        info.last_updated = Some(entry.updated_at);
+        info.covered_from = Some(entry.covered_from);
+    }
+
+    /// Snapshot of `provider`'s data for the on-disk cache, if it is complete.
+    pub fn cache_entry(&self, provider: ProviderId) -> Option<CacheEntry> {
+        let info = self.provider_info(provider);
+        if info.errors.cost.is_some() || info.errors.usage.is_some() {
+            return None;
+        }
+        Some(CacheEntry {
+            version: cache::CACHE_VERSION,
+            key_hash: info.key_hash?,
+            updated_at: info.last_updated?,
+            covered_from: info.covered_from?,
+            cost_data: info.cost_data.clone(),
+            usage_data: info.usage_data.clone(),
+            cost_buckets: info.cost_buckets.clone(),
+            usage_buckets: info.usage_buckets.clone(),
+            api_key_names: info.api_key_names.clone(),
+        })
+    }
+
+    /// Where the next fetch for `provider` should start, at which
+    /// granularity, and which API key names are already known, so refreshes
+    /// only request what's new.
+    pub fn fetch_window(
+        &self,
+        provider: ProviderId,
+    ) -> (DateTime<Utc>, BucketWidth, HashSet<String>) {
+        let info = self.provider_info(provider);
+        let width = self.fetch_width();
+        let start_time = match width {
+            BucketWidth::Day => {
+                let last_updated = if self.has_data(provider) {
+                    info.last_updated
+                } else {
+                    None
+                };
+                fetch::fetch_start_time(
+                    last_updated,
+                    info.covered_from,
+                    self.required_fetch_start(),
+                )
+            }
+            BucketWidth::Hour => fetch::hourly_start_time(),
+            BucketWidth::Minute => {
+                BucketWindow::ending_now(BucketWidth::Minute, fetch::LIVE_MINUTES).start
+            }
+        };
+        (
+            start_time,
+            width,
+            info.api_key_names.keys().cloned().collect(),
+        )
+    }
+
+    /// Whether the auto-refresh timer has run out and nothing is in flight.
+    /// The live view polls on its own, and its polls don't move the timer.
+    pub fn auto_refresh_due(&self) -> bool {
+        if self.current_view == View::Live {
+            return false;
+        }
+        let due = self
+            .auto_refresh
             .as_ref()
-            .and_then(|c| match c {
-                ProviderClient::Anthropic(client) => Some(client.clone()),
-                _ => None,
-            });
-        (openai_client, anthropic_client)
+            .and_then(|auto_refresh| auto_refresh.next_at)
+            .is_some_and(|next_at| next_at <= Utc::now());
+        let any_client = self
+            .provider_ids()
+            .into_iter()
+            .any(|id| self.has_client(id));
+        due && any_client && !self.any_loading()
+    }
+
+    /// Granularity a refresh of the current view requests.
+    pub fn fetch_width(&self) -> BucketWidth {
+        if self.current_view == View::Live {
+            BucketWidth::Minute
+        } else {
+            self.range.bucket_width()
+        }
+    }
+
+    /// Minute buckets shown by the live view, ending with the minute of the
+    /// last poll so the newest bucket isn't shown empty while the next one
+    /// is pending.
+    pub fn live_window(&self, provider: ProviderId) -> BucketWindow {
+        let polled_at = self.provider_info(provider).live_updated;
+        BucketWindow::ending_at(
+            BucketWidth::Minute,
+            fetch::LIVE_MINUTES,
+            polled_at.unwrap_or_else(Utc::now),
+        )
+    }
+
+    /// Dollars per hour at the pace of the last `BURN_RATE_MINUTES` full
+    /// minutes of the live view, priced from their tokens since cost is only
+    /// reported per day. The flag is set when some tokens had no price.
+    pub fn burn_rate_per_hour(&self, provider: ProviderId) -> Option<(f64, bool)> {
+        let info = self.provider_info(provider);
+        info.live_updated?;
+        // The window's last bucket is the minute still in progress.
+        let end = self.live_window(provider).end() - BucketWidth::Minute.duration();
+        let start = end - Duration::minutes(BURN_RATE_MINUTES);
+        let mut cost = 0.0;
+        let mut partial = false;
+        for d in info
+            .live_usage_data
+            .iter()
+            .filter(|d| d.date >= start && d.date < end)
+        {
+            match self.pricing.estimate(d) {
+                Some(estimate) => cost += estimate,
+                None => partial |= d.input_tokens + d.output_tokens > 0,
+            }
+        }
+        Some((cost * 60.0 / BURN_RATE_MINUTES as f64, partial))
+    }
+
+    pub fn provider_handle(&self, provider: ProviderId) -> Arc<dyn UsageProvider> {
+        self.provider_info(provider).provider.clone()
     }
 
     pub fn reset_filter(&mut self) {
@@ -381,46 +1535,70 @@ impl App {
         self.show_segment_values = !self.show_segment_values;
     }
 
+    pub fn toggle_anomalies(&mut self) {
+        self.show_anomalies = !self.show_anomalies;
+    }
+
     pub fn get_available_filters(&self) -> Vec<String> {
         let provider = self.current_provider();
-        let info = self.provider_info(provider);
-        let filtered_usage_data = self.filter_usage_data_by_range(&info.usage_data);
-        let filtered_cost_data = self.filter_cost_data_by_range(&info.cost_data);
+        let scope = self.scope();
+        let filtered_usage_data = if self.current_view == View::Live {
+            let window = self.live_window(provider);
+            self.provider_info(provider)
+                .live_usage_data
+                .iter()
+                .filter(|d| window.contains(d.date))
+                .cloned()
+                .collect()
+        } else {
+            self.filter_usage_data_by_range(&self.usage_data(scope))
+        };
+        let filtered_cost_data = self.filter_cost_data_by_range(&self.cost_data(scope));
 
         let mut filters: Vec<String> = match self.group_by {
-            GroupBy::Model => match self.current_view {
-                View::Cost => {
-                    let mut model_totals = HashMap::new();
-                    for cost in &filtered_cost_data {
-                        if let Some(ref line_item) = cost.line_item {
-                            let line_item = line_item.trim();
-                            if !line_item.is_empty() {
-                                *model_totals.entry(line_item.to_string()).or_insert(0.0) +=
-                                    cost.cost;
+            GroupBy::Model | GroupBy::Provider | GroupBy::ProviderModel => {
+                match self.current_view {
+                    View::Cost => {
+                        let mut model_totals = HashMap::new();
+                        for cost in &filtered_cost_data {
+                            if let Some(ref line_item) = cost.line_item {
+                                let line_item = line_item.trim();
+                                if !line_item.is_empty() {
+                                    *model_totals.entry(line_item.to_string()).or_insert(0.0) +=
+                                        cost.cost;
+                                }
                             }
                         }
+                        model_totals
+                            .into_iter()
+                            .filter(|(_, total)| *total >= shared::COST_THRESHOLD)
+                            .map(|(model, _)| model)
+                            .collect()
                     }
-                    model_totals
-                        .into_iter()
-                        .filter(|(_, total)| *total >= crate::ui::content::shared::COST_THRESHOLD)
-                        .map(|(model, _)| model)
-                        .collect()
-                }
-                View::Usage => {
-                    let mut models_with_usage = HashSet::new();
-                    for usage in &filtered_usage_data {
-                        if let Some(ref model) = usage.model {
-                            let model = model.trim();
-                            if !model.is_empty()
-                                && (usage.input_tokens > 0 || usage.output_tokens > 0)
-                            {
-                                models_with_usage.insert(model.to_string());
+                    View::Reconcile => {
+                        let models: HashSet<String> = self
+                            .reconciliation(scope)
+                            .into_iter()
+                            .map(|row| row.model)
+                            .collect();
+                        models.into_iter().collect()
+                    }
+                    View::Usage | View::Live => {
+                        let mut models_with_usage = HashSet::new();
+                        for usage in &filtered_usage_data {
+                            if let Some(ref model) = usage.model {
+                                let model = model.trim();
+                                if !model.is_empty()
+                                    && (usage.input_tokens > 0 || usage.output_tokens > 0)
+                                {
+                                    models_with_usage.insert(model.to_string());
+                                }
                             }
                         }
+                        models_with_usage.into_iter().collect()
                     }
-                    models_with_usage.into_iter().collect()
                 }
-            },
+            }
             GroupBy::ApiKeys => {
                 let mut api_keys_with_usage = HashSet::new();
                 for usage in &filtered_usage_data {
@@ -442,22 +1620,118 @@ impl App {
     }
 
     pub fn filter_usage_data_by_range(&self, data: &[DailyUsageData]) -> Vec<DailyUsageData> {
-        let latest_date = match data.iter().map(|d| d.date).max() {
-            Some(date) => date,
-            None => return Vec::new(),
-        };
-        let span = self.range.days().saturating_sub(1);
-        let cutoff = latest_date - Duration::days(span);
-        data.iter().filter(|d| d.date >= cutoff).cloned().collect()
+        let window = self.range_window();
+        data.iter()
+            .filter(|d| window.contains(d.date))
+            .cloned()
+            .collect()
     }
 
     pub fn filter_cost_data_by_range(&self, data: &[DailyData]) -> Vec<DailyData> {
-        let latest_date = match data.iter().map(|d| d.date).max() {
-            Some(date) => date,
-            None => return Vec::new(),
-        };
-        let span = self.range.days().saturating_sub(1);
-        let cutoff = latest_date - Duration::days(span);
-        data.iter().filter(|d| d.date >= cutoff).cloned().collect()
+        let window = self.range_window();
+        // Daily cost buckets can't be split into hours.
+        if window.width != BucketWidth::Day {
+            return Vec::new();
+        }
+        data.iter()
+            .filter(|d| window.contains(d.date))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Applies the result of an hourly fetch or live poll. Neither requests
+/// cost, so the cost error from before the fetch and the daily fetch's
+/// warnings and retries still stand.
+fn merge_usage_errors(info: &mut ProviderInfo, usage_error: Option<String>, warnings: Vec<String>) {
+    info.errors = crate::provider::ProviderErrors {
+        cost: info
+            .fetch
+            .as_ref()
+            .and_then(|fetch| fetch.previous_errors.cost.clone()),
+        usage: usage_error,
+    };
+    for warning in warnings {
+        if !info.warnings.contains(&warning) {
+            info.warnings.push(warning);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn span(window: DateWindow) -> (NaiveDate, NaiveDate) {
+        (window.start, window.end)
+    }
+
+    #[test]
+    fn date_window_ending() {
+        let window = DateWindow::ending(date(2024, 3, 10), 7);
+        assert_eq!(span(window), (date(2024, 3, 4), date(2024, 3, 10)));
+        assert_eq!(window.days(), 7);
+
+        // A window is never shorter than one day.
+        let window = DateWindow::ending(date(2024, 3, 10), 0);
+        assert_eq!(span(window), (date(2024, 3, 10), date(2024, 3, 10)));
+    }
+
+    #[test]
+    fn month_to_date_on_the_first() {
+        let window = DateWindow::month_to_date(date(2024, 3, 1));
+        assert_eq!(span(window), (date(2024, 3, 1), date(2024, 3, 1)));
+
+        let window = DateWindow::month_to_date(date(2024, 3, 15));
+        assert_eq!(span(window), (date(2024, 3, 1), date(2024, 3, 15)));
+    }
+
+    #[test]
+    fn previous_month_across_year_end() {
+        let window = DateWindow::previous_month(date(2024, 1, 15));
+        assert_eq!(span(window), (date(2023, 12, 1), date(2023, 12, 31)));
+
+        let window = DateWindow::previous_month(date(2024, 3, 1));
+        assert_eq!(span(window), (date(2024, 2, 1), date(2024, 2, 29)));
+    }
+
+    #[test]
+    fn date_window_previous() {
+        let window = DateWindow::ending(date(2024, 1, 3), 7).previous();
+        assert_eq!(span(window), (date(2023, 12, 21), date(2023, 12, 27)));
+    }
+
+    #[test]
+    fn bucket_window_ending_at() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 10, 14, 35, 0).unwrap();
+        let window = BucketWindow::ending_at(BucketWidth::Hour, 24, at);
+        let hour = |day, hour| Utc.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap();
+        assert_eq!(window.start, hour(9, 15));
+        assert_eq!(window.end(), hour(10, 15));
+        assert_eq!(window.buckets().count(), 24);
+    }
+
+    #[test]
+    fn bucket_window_contains_is_half_open() {
+        let window = BucketWindow::from_dates(DateWindow::ending(date(2024, 3, 10), 7));
+        assert!(window.contains(window.start));
+        assert!(window.contains(window.end() - Duration::seconds(1)));
+        assert!(!window.contains(window.end()));
+        assert!(!window.contains(window.start - Duration::seconds(1)));
+    }
+
+    #[test]
+    fn bucket_window_previous() {
+        let window = BucketWindow::from_dates(DateWindow::ending(date(2024, 1, 3), 7));
+        let previous = window.previous();
+        assert_eq!(previous.end(), window.start);
+        let expected = DateWindow::ending(date(2023, 12, 27), 7);
+        assert_eq!(previous.start, expected.start_time());
+        assert_eq!(previous.len, window.len);
     }
 }
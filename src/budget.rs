@@ -0,0 +1,341 @@
+use crate::provider::ProviderInfo;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_WARN_AT: f64 = 80.0;
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Period {
+    pub fn label(self) -> &'static str {
+        match self {
+            Period::Daily => "Daily",
+            Period::Weekly => "Weekly",
+            Period::Monthly => "Monthly",
+        }
+    }
+
+    /// Start of the period `today` falls in (UTC). Weeks start on Monday.
+    pub fn start(self, today: NaiveDate) -> DateTime<Utc> {
+        let first_day = match self {
+            Period::Daily => today,
+            Period::Weekly => today - Duration::days(today.weekday().num_days_from_monday() as i64),
+            Period::Monthly => today.with_day(1).unwrap(),
+        };
+        first_day.and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+}
+
+/// A spending limit from the config file.
+///
+/// ```toml
+/// [[budgets]]
+/// period = "monthly"
+/// limit = 500
+///
+/// [[budgets]]
+/// name = "Prod key"
+/// period = "daily"
+/// provider = "openai/prod"
+/// api_key = "prod-backend"
+/// tokens = 20_000_000
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    /// Shown instead of the generated label.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub period: Period,
+    /// Provider id, optionally narrowed to one account, e.g. `openai` or
+    /// `openai/prod`. Every account counts when unset.
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Counts models whose name starts with this, so `gpt-4o` also covers
+    /// `gpt-4o-mini`.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// API key name or id. Cost isn't reported per key, so these budgets
+    /// can only limit tokens.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Limit in dollars.
+    #[serde(default)]
+    pub limit: Option<f64>,
+    /// Limit in input plus output tokens.
+    #[serde(default)]
+    pub tokens: Option<u64>,
+    /// Percentage of the limit at which toktop starts warning.
+    #[serde(default)]
+    pub warn_at: Option<f64>,
+}
+
+impl Budget {
+    pub fn validate(&self) -> Result<()> {
+        let label = self.label();
+        match (self.limit, self.tokens) {
+            (Some(_), Some(_)) | (None, None) => {
+                bail!("Budget '{}' needs either a limit or tokens", label)
+            }
+            (Some(limit), None) if limit <= 0.0 => {
+                bail!("Budget '{}' needs a limit above zero", label)
+            }
+            (None, Some(0)) => bail!("Budget '{}' needs tokens above zero", label),
+            (Some(_), None) if self.api_key.is_some() => bail!(
+                "Budget '{}' limits cost per API key, which isn't reported; use tokens instead",
+                label
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// The budget's name, or its period and filters, e.g. "Monthly
+    /// openai/prod gpt-4o".
+    pub fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        let filters: Vec<&str> = [&self.provider, &self.model, &self.api_key]
+            .into_iter()
+            .filter_map(|filter| filter.as_deref())
+            .collect();
+        if filters.is_empty() {
+            format!("{} total", self.period.label())
+        } else {
+            format!("{} {}", self.period.label(), filters.join(" "))
+        }
+    }
+
+    pub fn warn_at(&self) -> f64 {
+        self.warn_at.unwrap_or(DEFAULT_WARN_AT)
+    }
+
+    pub fn covers(&self, info: &ProviderInfo) -> bool {
+        self.provider
+            .as_deref()
+            .is_none_or(|selector| info.matches(selector))
+    }
+
+    fn covers_model(&self, model: &Option<String>) -> bool {
+        match (&self.model, model) {
+            (None, _) => true,
+            (Some(wanted), Some(model)) => model
+                .trim()
+                .to_lowercase()
+                .starts_with(&wanted.to_lowercase()),
+            (Some(_), None) => false,
+        }
+    }
+
+    fn covers_api_key(&self, info: &ProviderInfo, api_key_id: &Option<String>) -> bool {
+        let Some(wanted) = &self.api_key else {
+            return true;
+        };
+        api_key_id.as_ref().is_some_and(|id| {
+            id == wanted
+                || info
+                    .api_key_names
+                    .get(id)
+                    .is_some_and(|name| name == wanted)
+        })
+    }
+
+    /// Dollars or tokens spent so far this period by the accounts the
+    /// budget covers.
+    pub fn spent(&self, accounts: &[&ProviderInfo], today: NaiveDate) -> f64 {
+        let since = self.period.start(today);
+        let covered = accounts.iter().filter(|info| self.covers(info));
+        if self.tokens.is_some() {
+            covered
+                .flat_map(|info| {
+                    info.usage_data.iter().filter(move |d| {
+                        d.date >= since
+                            && self.covers_model(&d.model)
+                            && self.covers_api_key(info, &d.api_key_id)
+                    })
+                })
+                .map(|d| (d.input_tokens + d.output_tokens) as f64)
+                .sum()
+        } else {
+            covered
+                .flat_map(|info| info.cost_data.iter())
+                .filter(|d| d.date >= since && self.covers_model(&d.line_item))
+                .map(|d| d.cost)
+                .sum()
+        }
+    }
+
+    /// The limit in the same unit as `spent`.
+    pub fn limit(&self) -> f64 {
+        self.tokens
+            .map_or(self.limit.unwrap_or(0.0), |tokens| tokens as f64)
+    }
+}
+
+/// How much of a budget is used up.
+pub struct BudgetStatus<'a> {
+    pub budget: &'a Budget,
+    pub spent: f64,
+}
+
+impl BudgetStatus<'_> {
+    pub fn percent(&self) -> f64 {
+        self.spent / self.budget.limit() * 100.0
+    }
+
+    pub fn breached(&self) -> bool {
+        self.spent >= self.budget.limit()
+    }
+
+    pub fn warning(&self) -> bool {
+        self.percent() >= self.budget.warn_at()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DailyData, DailyUsageData};
+    use crate::provider::openai::OpenAIProvider;
+    use crate::provider::Account;
+    use std::sync::Arc;
+
+    fn budget(toml: &str) -> Budget {
+        let budget: Budget = toml::from_str(toml).unwrap();
+        budget.validate().unwrap();
+        budget
+    }
+
+    fn day(day: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(2024, 3, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    fn account() -> ProviderInfo {
+        ProviderInfo::new(Account {
+            provider: Arc::new(OpenAIProvider::new(None)),
+            name: Some("prod".to_string()),
+            env_var: "OPENAI_ADMIN_KEY_PROD".to_string(),
+        })
+    }
+
+    fn cost(date: u32, cost: f64, model: &str) -> DailyData {
+        DailyData {
+            date: day(date),
+            cost,
+            line_item: Some(model.to_string()),
+        }
+    }
+
+    fn usage(date: u32, tokens: u64, api_key_id: &str) -> DailyUsageData {
+        DailyUsageData {
+            date: day(date),
+            input_tokens: tokens,
+            output_tokens: tokens,
+            api_key_id: Some(api_key_id.to_string()),
+            model: Some("gpt-4o".to_string()),
+            cache_read_input_tokens: None,
+            uncached_input_tokens: None,
+            cache_write_5m_tokens: None,
+            cache_write_1h_tokens: None,
+            num_requests: None,
+            batch: false,
+        }
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
+    }
+
+    #[test]
+    fn cost_spent_this_period() {
+        let mut info = account();
+        info.cost_data = vec![
+            cost(1, 10.0, "gpt-4o"),
+            cost(11, 2.0, "gpt-4o-mini"),
+            cost(12, 4.0, "o1"),
+            cost(15, 1.0, "gpt-4o"),
+        ];
+
+        let monthly = budget("period = \"monthly\"\nlimit = 100");
+        assert_eq!(monthly.spent(&[&info], today()), 17.0);
+
+        // Weeks start on Monday, 2024-03-11.
+        let weekly = budget("period = \"weekly\"\nlimit = 100\nmodel = \"GPT-4o\"");
+        assert_eq!(weekly.spent(&[&info], today()), 3.0);
+
+        let other = budget("period = \"monthly\"\nlimit = 100\nprovider = \"openai/dev\"");
+        assert_eq!(other.spent(&[&info], today()), 0.0);
+    }
+
+    #[test]
+    fn token_spent_by_api_key() {
+        let mut info = account();
+        info.api_key_names
+            .insert("key_1".to_string(), "prod-backend".to_string());
+        info.usage_data = vec![
+            usage(14, 100, "key_1"),
+            usage(15, 50, "key_1"),
+            usage(15, 7, "key_2"),
+        ];
+
+        let daily = budget("period = \"daily\"\ntokens = 1000\napi_key = \"prod-backend\"");
+        assert_eq!(daily.spent(&[&info], today()), 100.0);
+        assert_eq!(daily.limit(), 1000.0);
+
+        let by_id = budget("period = \"monthly\"\ntokens = 1000\napi_key = \"key_2\"");
+        assert_eq!(by_id.spent(&[&info], today()), 14.0);
+    }
+
+    #[test]
+    fn zero_limits_are_rejected() {
+        for toml in [
+            "period = \"daily\"\nlimit = 0",
+            "period = \"daily\"\nlimit = -5",
+            "period = \"daily\"\ntokens = 0",
+            "period = \"daily\"",
+            "period = \"daily\"\nlimit = 5\ntokens = 5",
+            "period = \"daily\"\nlimit = 5\napi_key = \"prod\"",
+        ] {
+            let budget: Budget = toml::from_str(toml).unwrap();
+            assert!(budget.validate().is_err(), "{}", toml);
+        }
+    }
+
+    #[test]
+    fn status_thresholds() {
+        let budget = budget("period = \"monthly\"\nlimit = 200");
+        let status = |spent| BudgetStatus {
+            budget: &budget,
+            spent,
+        };
+
+        assert_eq!(status(0.0).percent(), 0.0);
+        assert!(!status(0.0).warning() && !status(0.0).breached());
+        assert!(!status(159.0).warning());
+        assert!(status(160.0).warning() && !status(160.0).breached());
+        assert!(status(200.0).breached());
+        assert_eq!(status(300.0).percent(), 150.0);
+    }
+
+    #[test]
+    fn token_status_uses_token_limit() {
+        let budget = budget("period = \"daily\"\ntokens = 1_000\nwarn_at = 50");
+        let status = BudgetStatus {
+            budget: &budget,
+            spent: 500.0,
+        };
+        assert_eq!(status.percent(), 50.0);
+        assert!(status.warning() && !status.breached());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::config;
+use crate::models::{DailyData, DailyUsageData};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// Bump whenever the layout of `DailyData`/`DailyUsageData` changes so old
+// cache files are ignored instead of failing to parse.
+pub const CACHE_VERSION: u32 = 5;
+
+#[derive(Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// `CACHE_VERSION` when the entry was written.
+    pub version: u32,
+    /// `key_hash` of the admin key the data was fetched with.
+    pub key_hash: u64,
+    pub updated_at: DateTime<Utc>,
+    pub covered_from: DateTime<Utc>,
+    pub cost_data: Vec<DailyData>,
+    pub usage_data: Vec<DailyUsageData>,
+    pub cost_buckets: Vec<DateTime<Utc>>,
+    pub usage_buckets: Vec<DateTime<Utc>>,
+    pub api_key_names: HashMap<String, String>,
+}
+
+/// Fingerprint of an admin key, so a cache is only restored for the key that
+/// filled it. FNV-1a rather than `DefaultHasher`, whose output may change
+/// between Rust releases.
+pub fn key_hash(api_key: &str) -> u64 {
+    api_key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn cache_path(provider_id: &str) -> Option<PathBuf> {
+    config::xdg_dir("XDG_CACHE_HOME", ".cache")
+        .map(|dir| dir.join("toktop").join(format!("{}.json", provider_id)))
+}
+
+/// Reads the last successful fetch for `provider_id`, if there is a usable one
+/// made with the key hashing to `key_hash`.
+pub fn load(provider_id: &str, key_hash: u64) -> Option<CacheEntry> {
+    let text = std::fs::read_to_string(cache_path(provider_id)?).ok()?;
+    let entry: CacheEntry = serde_json::from_str(&text).ok()?;
+    (entry.version == CACHE_VERSION && entry.key_hash == key_hash).then_some(entry)
+}
+
+pub fn save(provider_id: &str, entry: &CacheEntry) -> Result<()> {
+    let path = cache_path(provider_id).context("Could not determine cache directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create cache directory '{}'", dir.display()))?;
+    }
+
+    let text = serde_json::to_string(entry)?;
+
+    // Write to a temporary file first so a crash never leaves a truncated cache behind.
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, text)
+        .with_context(|| format!("Failed to write cache file '{}'", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to write cache file '{}'", path.display()))?;
+    Ok(())
+}
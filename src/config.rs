@@ -0,0 +1,162 @@
+use crate::budget::Budget;
+use crate::notify::NotifyConfig;
+use crate::pricing::ModelPrice;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Settings read from `~/.config/toktop/config.toml`.
+///
+/// ```toml
+/// refresh_interval = "5m"
+///
+/// [providers.openai]
+/// base_url = "https://llm-gateway.internal/openai"
+/// accounts = ["prod", "staging"]
+///
+/// [[budgets]]
+/// period = "monthly"
+/// limit = 500
+///
+/// [pricing."gpt-4o"]
+/// input = 2.50
+/// output = 10.00
+/// ```
+#[derive(Deserialize, Default)]
+pub struct Config {
+    /// How often to refresh automatically, see `parse_interval`.
+    #[serde(default)]
+    pub refresh_interval: Option<String>,
+    #[serde(default)]
+    pub providers: HashMap<String, ProviderConfig>,
+    #[serde(default)]
+    pub budgets: Vec<Budget>,
+    #[serde(default)]
+    pub notifications: NotifyConfig,
+    /// Per-model prices replacing or adding to the built-in ones.
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPrice>,
+    #[serde(skip)]
+    base_url_overrides: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Default, Clone)]
+pub struct ProviderConfig {
+    /// API root the client sends requests to, e.g. `https://api.openai.com`.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Named accounts, each with its own admin key in `<ENV_VAR>_<NAME>`.
+    #[serde(default)]
+    pub accounts: Vec<String>,
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("toktop").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file '{}'", path.display()))?;
+        let config: Self = toml::from_str(&text)
+            .with_context(|| format!("Failed to parse config file '{}'", path.display()))?;
+        config
+            .notifications
+            .validate()
+            .with_context(|| format!("Invalid config file '{}'", path.display()))?;
+        Ok(config)
+    }
+
+    /// Overrides the base URL for `provider_id`, taking precedence over both
+    /// the environment and the config file. Used for `--base-url`.
+    pub fn override_base_url(&mut self, provider_id: &str, base_url: String) {
+        self.base_url_overrides
+            .insert(provider_id.to_string(), base_url);
+    }
+
+    /// Resolves the base URL for `provider_id` from, in order, `--base-url`,
+    /// `$TOKTOP_<ID>_BASE_URL` and the config file.
+    pub fn base_url(&self, provider_id: &str) -> Option<String> {
+        self.base_url_overrides
+            .get(provider_id)
+            .cloned()
+            .or_else(|| std::env::var(base_url_env_var(provider_id)).ok())
+            .or_else(|| {
+                self.providers
+                    .get(provider_id)
+                    .and_then(|p| p.base_url.clone())
+            })
+            .filter(|url| !url.trim().is_empty())
+    }
+
+    /// Account names listed for `provider_id` in the config file.
+    pub fn accounts(&self, provider_id: &str) -> &[String] {
+        self.providers
+            .get(provider_id)
+            .map_or(&[], |p| p.accounts.as_slice())
+    }
+}
+
+/// Parses intervals like `90`, `90s`, `5m` or `1h`. `0` and `off` return a
+/// zero duration, meaning disabled.
+pub fn parse_interval(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("off") {
+        return Ok(Duration::ZERO);
+    }
+    let (number, unit_secs) = match s.char_indices().last() {
+        Some((idx, 's')) => (&s[..idx], 1),
+        Some((idx, 'm')) => (&s[..idx], 60),
+        Some((idx, 'h')) => (&s[..idx], 60 * 60),
+        _ => (s, 1),
+    };
+    let Ok(value) = number.trim().parse::<u64>() else {
+        bail!("Invalid interval '{}', expected e.g. 90s, 5m or 1h", s);
+    };
+    // Intervals are scheduled with chrono, so they must fit its range too.
+    match value.checked_mul(unit_secs).map(Duration::from_secs) {
+        Some(interval) if chrono::Duration::from_std(interval).is_ok() => Ok(interval),
+        _ => bail!("Interval '{}' is too long", s),
+    }
+}
+
+pub fn base_url_env_var(provider_id: &str) -> String {
+    format!("TOKTOP_{}_BASE_URL", provider_id.to_uppercase())
+}
+
+pub fn xdg_dir(var: &str, home_fallback: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_units() {
+        assert_eq!(parse_interval("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_interval("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_interval(" 5m ").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_interval("1h").unwrap(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn interval_disabled() {
+        assert_eq!(parse_interval("0").unwrap(), Duration::ZERO);
+        assert_eq!(parse_interval("OFF").unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn interval_rejected() {
+        for input in ["", "m", "5 minutes", "-5m", "1.5h", "5d"] {
+            assert!(parse_interval(input).is_err(), "{}", input);
+        }
+        assert!(parse_interval(&format!("{}h", u64::MAX)).is_err());
+        assert!(parse_interval(&format!("{}s", u64::MAX)).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::app::{BucketWindow, DateWindow, GroupBy};
+use crate::fetch;
+use crate::models::{BucketWidth, DailyData, DailyUsageData};
+use crate::notify::{self, Notification, NotifyConfig};
+use crate::provider::{self, Account, ProviderId, ProviderInfo};
+use crate::ui::content::shared;
+use crate::ui::summary::{compare_periods, summarize_cost, summarize_usage};
+use crate::ui::utils::format_tokens;
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, NaiveDate, Utc};
+use clap::Args;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+
+const TOP_ENTRIES: usize = 5;
+/// Days of history the digest needs: the week up to its day and the week
+/// before, for the week-over-week change.
+pub const HISTORY_DAYS: i64 = 14;
+
+#[derive(Args)]
+pub struct DigestArgs {
+    /// Provider to include, optionally narrowed to one account, e.g. openai
+    /// or openai/prod (defaults to every account with a key)
+    #[arg(long)]
+    provider: Option<String>,
+
+    /// Print the digest instead of sending it to the configured channels
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Yesterday's spend, summed the same way as the summary panel.
+#[derive(Serialize)]
+pub struct Digest {
+    pub date: NaiveDate,
+    pub total_cost: f64,
+    pub providers: Vec<ProviderCost>,
+    pub top_models: Vec<TokenTotal>,
+    pub top_api_keys: Vec<TokenTotal>,
+    /// The 7 days up to `date` against the 7 days before, in percent.
+    /// `None` when there's nothing to compare with.
+    pub cost_change_pct: Option<f64>,
+    pub token_change_pct: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct ProviderCost {
+    pub provider: String,
+    pub cost: f64,
+}
+
+#[derive(Serialize)]
+pub struct TokenTotal {
+    pub provider: String,
+    pub name: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl TokenTotal {
+    fn total(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+impl Digest {
+    pub fn new(accounts: &[&ProviderInfo], date: NaiveDate) -> Self {
+        let day = BucketWindow::from_dates(DateWindow {
+            start: date,
+            end: date,
+        });
+
+        let providers: Vec<ProviderCost> = accounts
+            .iter()
+            .map(|info| ProviderCost {
+                provider: info.label(),
+                cost: summarize_cost(&info.cost_data, day, None),
+            })
+            .collect();
+
+        let mut top_models = Vec::new();
+        let mut top_api_keys = Vec::new();
+        for info in accounts {
+            let in_day = || info.usage_data.iter().filter(|d| day.contains(d.date));
+            let models: BTreeSet<String> = in_day()
+                .filter_map(|d| shared::extract_trimmed_string(&d.model))
+                .map(str::to_string)
+                .collect();
+            for model in models {
+                let (input_tokens, output_tokens) =
+                    summarize_usage(&info.usage_data, day, Some(&model), GroupBy::Model);
+                top_models.push(TokenTotal {
+                    provider: info.label(),
+                    name: model,
+                    input_tokens,
+                    output_tokens,
+                });
+            }
+            let key_ids: BTreeSet<String> = in_day()
+                .filter_map(|d| shared::extract_trimmed_string(&d.api_key_id))
+                .map(str::to_string)
+                .collect();
+            for id in key_ids {
+                let (input_tokens, output_tokens) =
+                    summarize_usage(&info.usage_data, day, Some(&id), GroupBy::ApiKeys);
+                top_api_keys.push(TokenTotal {
+                    provider: info.label(),
+                    name: info
+                        .api_key_names
+                        .get(&id)
+                        .cloned()
+                        .unwrap_or_else(|| shared::abbreviate_api_key(&id)),
+                    input_tokens,
+                    output_tokens,
+                });
+            }
+        }
+        for top in [&mut top_models, &mut top_api_keys] {
+            top.sort_by_key(|entry| std::cmp::Reverse(entry.total()));
+            top.truncate(TOP_ENTRIES);
+        }
+
+        let week = BucketWindow::from_dates(DateWindow::ending(date, 7));
+        let cost_data: Vec<DailyData> = accounts
+            .iter()
+            .flat_map(|info| info.cost_data.iter().cloned())
+            .collect();
+        let usage_data: Vec<DailyUsageData> = accounts
+            .iter()
+            .flat_map(|info| info.usage_data.iter().cloned())
+            .collect();
+        let cost_change = compare_periods(
+            &cost_data,
+            week,
+            |d| d.date,
+            |d| d.cost,
+            None,
+            |d| shared::extract_trimmed_string(&d.line_item),
+        );
+        let token_change = compare_periods(
+            &usage_data,
+            week,
+            |d| d.date,
+            |d| (d.input_tokens + d.output_tokens) as f64,
+            None,
+            |d| shared::extract_trimmed_string(&d.model),
+        );
+
+        Self {
+            date,
+            total_cost: providers.iter().map(|p| p.cost).sum(),
+            providers,
+            top_models,
+            top_api_keys,
+            cost_change_pct: cost_change.map(|(pct, _)| pct),
+            token_change_pct: token_change.map(|(pct, _)| pct),
+        }
+    }
+
+    /// Plain text for Slack and email, one fact per line.
+    pub fn text(&self) -> String {
+        let mut lines = vec![format!("Cost: ${:.2}", self.total_cost)];
+        for p in &self.providers {
+            lines.push(format!("  {}: ${:.2}", p.provider, p.cost));
+        }
+        let change = |pct: Option<f64>| match pct {
+            Some(pct) if pct >= 0.0 => format!("↑ {:.1}%", pct),
+            Some(pct) => format!("↓ {:.1}%", pct.abs()),
+            None => "n/a".to_string(),
+        };
+        lines.push(format!(
+            "Last 7 days vs the 7 before: cost {}, tokens {}",
+            change(self.cost_change_pct),
+            change(self.token_change_pct)
+        ));
+        for (title, entries) in [
+            ("Top models", &self.top_models),
+            ("Top API keys", &self.top_api_keys),
+        ] {
+            if entries.is_empty() {
+                continue;
+            }
+            lines.push(format!("{}:", title));
+            for entry in entries {
+                lines.push(format!(
+                    "  {} {}: {} tokens ({} in, {} out)",
+                    entry.provider,
+                    entry.name,
+                    format_tokens(entry.total()),
+                    format_tokens(entry.input_tokens),
+                    format_tokens(entry.output_tokens)
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Fetches the last `HISTORY_DAYS` days and sends yesterday's digest to
+/// every channel, or prints it for `--dry-run`.
+pub async fn run(accounts: Vec<Account>, notify: NotifyConfig, args: DigestArgs) -> Result<()> {
+    if !args.dry_run && notify.channels.is_empty() {
+        bail!("No notification channels are configured; use --dry-run to print the digest");
+    }
+
+    let mut accounts = provider::connect_accounts(accounts, args.provider.as_deref())?;
+    let yesterday = Utc::now().date_naive() - Duration::days(1);
+    let start = DateWindow::ending(yesterday, HISTORY_DAYS).start_time();
+    let tasks: Vec<_> = accounts
+        .iter()
+        .enumerate()
+        .map(|(i, info)| {
+            tokio::spawn(fetch::fetch_data(
+                ProviderId(i),
+                info.provider.clone(),
+                start,
+                BucketWidth::Day,
+                HashSet::new(),
+            ))
+        })
+        .collect();
+    let mut errors = Vec::new();
+    for task in tasks {
+        let outcome = task.await.context("Fetch task failed")?;
+        let info = &mut accounts[outcome.provider.0];
+        for error in [&outcome.errors.cost, &outcome.errors.usage]
+            .into_iter()
+            .flatten()
+        {
+            errors.push(format!("{}: {}", info.label(), error));
+        }
+        info.cost_data = outcome.cost_data.map(|r| r.data).unwrap_or_default();
+        info.usage_data = outcome.usage_data.map(|r| r.data).unwrap_or_default();
+        info.api_key_names = outcome.api_key_names;
+    }
+    // A digest with a provider missing would understate the day.
+    if !errors.is_empty() {
+        bail!("Some data could not be fetched: {}", errors.join("; "));
+    }
+
+    let infos: Vec<&ProviderInfo> = accounts.iter().collect();
+    let notification = Notification::Digest(Digest::new(&infos, yesterday));
+    if args.dry_run {
+        println!("{}\n{}", notification.title(), notification.text());
+        return Ok(());
+    }
+    notify::send(&notify.channels, &notification).await
+}
@@ -1,4 +1,4 @@
-use crate::app::{App, OptionsColumn};
+use crate::app::{App, OptionsColumn, Range};
 use crossterm::event::KeyCode;
 
 pub enum EventAction {
@@ -8,6 +8,10 @@ pub enum EventAction {
 }
 
 pub fn handle_key_event(app: &mut App, key_code: KeyCode) -> EventAction {
+    if app.custom_range_input.is_some() {
+        return handle_custom_range_key(app, key_code);
+    }
+
     let popup_active = app.api_key_popup_active.is_some();
 
     match key_code {
@@ -21,13 +25,17 @@ pub fn handle_key_event(app: &mut App, key_code: KeyCode) -> EventAction {
             let provider_before = app.current_provider();
             app.move_column_cursor(delta);
 
+            if app.options_column == OptionsColumn::Range && app.range_needs_fetch() {
+                return EventAction::Refresh;
+            }
+
             if provider_before != app.current_provider() {
                 let new_provider = app.current_provider();
                 if !app.has_client(new_provider) {
                     app.show_api_key_popup(new_provider);
                 } else {
                     app.cancel_api_key_popup();
-                    if !app.initial_fetch_done(new_provider) {
+                    if !app.initial_fetch_done(new_provider) && !app.is_loading(new_provider) {
                         return EventAction::Refresh;
                     }
                 }
@@ -45,7 +53,17 @@ pub fn handle_key_event(app: &mut App, key_code: KeyCode) -> EventAction {
             app.toggle_group_by_expansion();
             EventAction::None
         }
+        KeyCode::Enter
+            if app.options_column == OptionsColumn::Range && app.range == Range::Custom =>
+        {
+            app.open_custom_range_popup();
+            EventAction::None
+        }
         KeyCode::Esc if popup_active => EventAction::Quit,
+        KeyCode::Esc if app.any_loading() => {
+            app.cancel_fetches();
+            EventAction::None
+        }
         _ if popup_active => {
             app.handle_api_key_input(key_code);
             EventAction::None
@@ -62,8 +80,32 @@ pub fn handle_key_event(app: &mut App, key_code: KeyCode) -> EventAction {
             app.toggle_segment_values();
             EventAction::None
         }
+        KeyCode::Char('a') | KeyCode::Char('A') => {
+            app.toggle_anomalies();
+            EventAction::None
+        }
         KeyCode::Char('r') | KeyCode::Char('R') => EventAction::Refresh,
         KeyCode::Char('q') | KeyCode::Char('Q') => EventAction::Quit,
         _ => EventAction::None,
     }
 }
+
+fn handle_custom_range_key(app: &mut App, key_code: KeyCode) -> EventAction {
+    match key_code {
+        KeyCode::Enter => {
+            if app.submit_custom_range() && app.range_needs_fetch() {
+                EventAction::Refresh
+            } else {
+                EventAction::None
+            }
+        }
+        KeyCode::Esc => {
+            app.cancel_custom_range_popup();
+            EventAction::None
+        }
+        _ => {
+            app.handle_custom_range_input(key_code);
+            EventAction::None
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use crate::app::{BucketWindow, DateWindow};
+use crate::fetch;
+use crate::models::{BucketWidth, DailyData, DailyUsageData};
+use crate::pricing::Pricing;
+use crate::provider::{self, Account, FetchOutcome, ProviderId, ProviderInfo};
+use crate::reconcile::{self, Reconciliation};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Provider to export, optionally narrowed to one account, e.g. openai or
+    /// openai/prod (defaults to every account with a key)
+    #[arg(long)]
+    provider: Option<String>,
+
+    /// 24h, 72h, 7d, 30d (any number of hours or days), mtd, last-month, or
+    /// dates as START..END
+    #[arg(long, default_value = "30d", value_parser = parse_range)]
+    range: BucketWindow,
+
+    #[arg(long, value_enum, default_value_t = Metric::Usage)]
+    metric: Metric,
+
+    #[arg(long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+
+    #[arg(long = "group-by", value_enum, default_value_t = GroupBy::Model)]
+    group_by: GroupBy,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Metric {
+    Cost,
+    Usage,
+    /// Billed cost against cost estimated from tokens, per day and model
+    Reconcile,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GroupBy {
+    Model,
+    #[value(name = "api_key", alias = "api-key")]
+    ApiKey,
+}
+
+#[derive(Serialize)]
+struct CostRow {
+    date: DateTime<Utc>,
+    provider: String,
+    line_item: Option<String>,
+    cost: f64,
+}
+
+/// A `DailyUsageData` row with the account it came from and the name of its
+/// API key. Grouping by model leaves the key columns empty and vice versa.
+#[derive(Serialize)]
+struct UsageRow {
+    date: DateTime<Utc>,
+    provider: String,
+    model: Option<String>,
+    api_key_id: Option<String>,
+    api_key_name: Option<String>,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_input_tokens: Option<u64>,
+    uncached_input_tokens: Option<u64>,
+    num_requests: Option<u64>,
+}
+
+/// How far back a `--range` of hours or days may reach, about ten years.
+/// Keeps the window's start representable.
+const MAX_RANGE_DAYS: i64 = 3650;
+
+fn parse_range(s: &str) -> Result<BucketWindow, String> {
+    let today = Utc::now().date_naive();
+    let s = s.trim().to_lowercase();
+    match s.as_str() {
+        "mtd" => return Ok(BucketWindow::from_dates(DateWindow::month_to_date(today))),
+        "last-month" => return Ok(BucketWindow::from_dates(DateWindow::previous_month(today))),
+        _ => {}
+    }
+    if let Some((start, end)) = s.split_once("..") {
+        let parse = |s: &str| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d");
+        return match (parse(start), parse(end)) {
+            (Ok(start), Ok(end)) if start <= end && end <= today => {
+                Ok(BucketWindow::from_dates(DateWindow { start, end }))
+            }
+            (Ok(_), Ok(_)) => Err("expected START..END with START <= END <= today".to_string()),
+            _ => Err(format!(
+                "expected dates as YYYY-MM-DD..YYYY-MM-DD, got '{}'",
+                s
+            )),
+        };
+    }
+    let count = |number: &str| number.parse::<i64>().ok().filter(|n| *n > 0);
+    if let Some(hours) = s.strip_suffix('h').and_then(count) {
+        if hours > MAX_RANGE_DAYS * 24 {
+            return Err(format!("ranges can span at most {} hours", MAX_RANGE_DAYS * 24));
+        }
+        return Ok(BucketWindow::ending_now(BucketWidth::Hour, hours));
+    }
+    if let Some(days) = s.strip_suffix('d').and_then(count) {
+        if days > MAX_RANGE_DAYS {
+            return Err(format!("ranges can span at most {} days", MAX_RANGE_DAYS));
+        }
+        return Ok(BucketWindow::from_dates(DateWindow::ending(today, days)));
+    }
+    Err(format!(
+        "expected e.g. 24h, 30d, mtd, last-month or 2024-01-01..2024-01-31, got '{}'",
+        s
+    ))
+}
+
+/// Fetches the selected accounts the same way the TUI does and writes the
+/// rows to stdout. Rows that could be fetched are written even if some
+/// requests failed; the failures are returned afterwards.
+pub async fn run(accounts: Vec<Account>, pricing: Pricing, args: ExportArgs) -> Result<()> {
+    if matches!(args.metric, Metric::Cost | Metric::Reconcile) {
+        if args.range.width != BucketWidth::Day {
+            bail!("Cost is only reported per day; pick a range in days");
+        }
+        if args.group_by == GroupBy::ApiKey {
+            bail!("Cost can't be grouped by API key");
+        }
+    }
+
+    let accounts = provider::connect_accounts(accounts, args.provider.as_deref())?;
+    let tasks: Vec<_> = accounts
+        .iter()
+        .enumerate()
+        .map(|(i, info)| {
+            tokio::spawn(fetch::fetch_data(
+                ProviderId(i),
+                info.provider.clone(),
+                args.range.start,
+                args.range.width,
+                HashSet::new(),
+            ))
+        })
+        .collect();
+    let mut outcomes = Vec::new();
+    for task in tasks {
+        outcomes.push(task.await.context("Fetch task failed")?);
+    }
+
+    let mut errors = Vec::new();
+    for outcome in &outcomes {
+        let label = accounts[outcome.provider.0].label();
+        let failed = match args.metric {
+            Metric::Cost => vec![&outcome.errors.cost],
+            Metric::Usage => vec![&outcome.errors.usage],
+            Metric::Reconcile => vec![&outcome.errors.cost, &outcome.errors.usage],
+        };
+        for error in failed.into_iter().flatten() {
+            errors.push(format!("{}: {}", label, error));
+        }
+        for warning in &outcome.warnings {
+            eprintln!("Warning: {}: {}", label, warning);
+        }
+    }
+
+    let stdout = io::stdout();
+    match args.metric {
+        Metric::Cost => write_rows(
+            stdout.lock(),
+            args.format,
+            cost_rows(&accounts, &outcomes, args.range),
+        )?,
+        Metric::Usage => write_rows(
+            stdout.lock(),
+            args.format,
+            usage_rows(&accounts, &outcomes, args.range, args.group_by),
+        )?,
+        Metric::Reconcile => write_rows(
+            stdout.lock(),
+            args.format,
+            reconcile_rows(&accounts, &outcomes, &pricing, args.range),
+        )?,
+    }
+
+    if !errors.is_empty() {
+        bail!("Some data could not be fetched: {}", errors.join("; "));
+    }
+    Ok(())
+}
+
+fn cost_rows(
+    accounts: &[ProviderInfo],
+    outcomes: &[FetchOutcome],
+    window: BucketWindow,
+) -> Vec<CostRow> {
+    let mut totals: BTreeMap<(DateTime<Utc>, String, Option<String>), f64> = BTreeMap::new();
+    for outcome in outcomes {
+        let label = accounts[outcome.provider.0].label();
+        let data: &[DailyData] = outcome
+            .cost_data
+            .as_ref()
+            .map_or(&[], |report| &report.data);
+        for d in data.iter().filter(|d| window.contains(d.date)) {
+            *totals
+                .entry((d.date, label.clone(), d.line_item.clone()))
+                .or_insert(0.0) += d.cost;
+        }
+    }
+    totals
+        .into_iter()
+        .map(|((date, provider, line_item), cost)| CostRow {
+            date,
+            provider,
+            line_item,
+            cost,
+        })
+        .collect()
+}
+
+fn usage_rows(
+    accounts: &[ProviderInfo],
+    outcomes: &[FetchOutcome],
+    window: BucketWindow,
+    group_by: GroupBy,
+) -> Vec<UsageRow> {
+    let mut rows: BTreeMap<(DateTime<Utc>, String, Option<String>), UsageRow> = BTreeMap::new();
+    for outcome in outcomes {
+        let label = accounts[outcome.provider.0].label();
+        let data: &[DailyUsageData] = outcome
+            .usage_data
+            .as_ref()
+            .map_or(&[], |report| &report.data);
+        for d in data.iter().filter(|d| window.contains(d.date)) {
+            let (model, api_key_id) = match group_by {
+                GroupBy::Model => (d.model.clone(), None),
+                GroupBy::ApiKey => (None, d.api_key_id.clone()),
+            };
+            let key = (d.date, label.clone(), model.clone().or(api_key_id.clone()));
+            let row = rows.entry(key).or_insert_with(|| UsageRow {
+                date: d.date,
+                provider: label.clone(),
+                api_key_name: api_key_id
+                    .as_ref()
+                    .and_then(|id| outcome.api_key_names.get(id).cloned()),
+                model,
+                api_key_id,
+                input_tokens: 0,
+                output_tokens: 0,
+                cache_read_input_tokens: None,
+                uncached_input_tokens: None,
+                num_requests: None,
+            });
+            row.input_tokens += d.input_tokens;
+            row.output_tokens += d.output_tokens;
+            row.cache_read_input_tokens =
+                add(row.cache_read_input_tokens, d.cache_read_input_tokens);
+            row.uncached_input_tokens = add(row.uncached_input_tokens, d.uncached_input_tokens);
+            row.num_requests = add(row.num_requests, d.num_requests);
+        }
+    }
+    rows.into_values().collect()
+}
+
+fn reconcile_rows(
+    accounts: &[ProviderInfo],
+    outcomes: &[FetchOutcome],
+    pricing: &Pricing,
+    window: BucketWindow,
+) -> Vec<Reconciliation> {
+    let mut rows: Vec<Reconciliation> = outcomes
+        .iter()
+        .flat_map(|outcome| {
+            reconcile::reconcile(
+                &accounts[outcome.provider.0].label(),
+                outcome.cost_data.as_ref().map_or(&[], |report| &report.data),
+                outcome.usage_data.as_ref().map_or(&[], |report| &report.data),
+                pricing,
+                window,
+            )
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        (a.date, &a.provider, &a.model).cmp(&(b.date, &b.provider, &b.model))
+    });
+    rows
+}
+
+/// Sums counts only some providers report, staying `None` if none did.
+fn add(total: Option<u64>, value: Option<u64>) -> Option<u64> {
+    match (total, value) {
+        (None, None) => None,
+        _ => Some(total.unwrap_or(0) + value.unwrap_or(0)),
+    }
+}
+
+fn write_rows<T: Serialize>(mut out: impl Write, format: Format, rows: Vec<T>) -> Result<()> {
+    match format {
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            for row in &rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &rows)?;
+            writeln!(out)?;
+        }
+        Format::Ndjson => {
+            for row in &rows {
+                serde_json::to_writer(&mut out, row)?;
+                writeln!(out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_in_hours_and_days() {
+        let hours = parse_range("24h").unwrap();
+        assert_eq!((hours.len, hours.width), (24, BucketWidth::Hour));
+
+        let days = parse_range(" 30D ").unwrap();
+        assert_eq!((days.len, days.width), (30, BucketWidth::Day));
+        assert_eq!(parse_range("mtd").unwrap().width, BucketWidth::Day);
+        assert_eq!(parse_range("last-month").unwrap().width, BucketWidth::Day);
+    }
+
+    #[test]
+    fn range_is_capped() {
+        let longest = parse_range(&format!("{}d", MAX_RANGE_DAYS)).unwrap();
+        assert_eq!(longest.len, MAX_RANGE_DAYS);
+        assert!(parse_range(&format!("{}d", MAX_RANGE_DAYS + 1)).is_err());
+        assert!(parse_range(&format!("{}h", MAX_RANGE_DAYS * 24)).is_ok());
+        assert!(parse_range(&format!("{}h", MAX_RANGE_DAYS * 24 + 1)).is_err());
+    }
+
+    #[test]
+    fn explicit_dates() {
+        let range = parse_range("2024-01-01..2024-01-31").unwrap();
+        assert_eq!((range.len, range.width), (31, BucketWidth::Day));
+        assert_eq!(parse_range("2024-01-05..2024-01-05").unwrap().len, 1);
+
+        assert!(parse_range("2024-01-31..2024-01-01").is_err());
+        let tomorrow = Utc::now().date_naive() + chrono::Duration::days(1);
+        assert!(parse_range(&format!("2024-01-01..{}", tomorrow)).is_err());
+        assert!(parse_range("2024-01-01..jan").is_err());
+    }
+
+    #[test]
+    fn range_garbage() {
+        for input in ["", "0d", "-3d", "1.5h", "week", "30"] {
+            assert!(parse_range(input).is_err(), "{}", input);
+        }
+    }
+}
@@ -1,38 +1,76 @@
-use crate::api::{anthropic::AnthropicClient, openai::OpenAIClient};
-use crate::models::{DailyData, DailyUsageData};
-use crate::provider::{Provider, ProviderErrors};
+use crate::models::BucketWidth;
+use crate::provider::{FetchOutcome, ProviderErrors, ProviderId, UsageProvider};
 use chrono::{DateTime, Duration, Utc};
 use std::collections::{HashMap, HashSet};
-
-const DAYS_TO_FETCH: i64 = 30;
-const CENTS_TO_DOLLARS: f64 = 100.0;
-
-fn timestamp_to_date(timestamp: i64) -> DateTime<Utc> {
-    DateTime::from_timestamp(timestamp, 0)
-        .unwrap_or(Utc::now() - Duration::days(DAYS_TO_FETCH))
-        .date_naive()
+use std::sync::Arc;
+
+// Always fetch at least this much history so switching between the shorter
+// ranges never needs another round trip.
+const DEFAULT_DAYS_TO_FETCH: i64 = 30;
+// Usage and cost buckets can keep changing for a while after the day ends, so
+// incremental refreshes always re-request this many trailing days.
+const REFETCH_TRAILING_DAYS: i64 = 2;
+// Hourly data is small, so it's always refetched in full and covers the
+// longest hourly range.
+pub const HOURS_TO_FETCH: i64 = 72;
+// Minutes of throughput the live view keeps on screen.
+pub const LIVE_MINUTES: i64 = 60;
+
+pub fn default_start_time() -> DateTime<Utc> {
+    let now = Utc::now();
+    (now.date_naive() - Duration::days(DEFAULT_DAYS_TO_FETCH))
         .and_hms_opt(0, 0, 0)
         .unwrap()
         .and_utc()
 }
 
-pub async fn fetch_data(
-    provider: Provider,
-    openai_client: Option<OpenAIClient>,
-    anthropic_client: Option<AnthropicClient>,
-) -> crate::provider::FetchOutcome {
-    match provider {
-        Provider::OpenAI => fetch_openai_data(openai_client).await,
-        Provider::Anthropic => fetch_anthropic_data(anthropic_client).await,
+/// Start of the oldest hourly bucket shown, so the current hour is the last of
+/// `HOURS_TO_FETCH` buckets.
+pub fn hourly_start_time() -> DateTime<Utc> {
+    BucketWidth::Hour.truncate(Utc::now()) - Duration::hours(HOURS_TO_FETCH - 1)
+}
+
+/// Where the cost report of a refresh at `width` starts. Cost is only
+/// reported per day, so hourly refreshes and live polls skip it: the hourly
+/// ranges don't show cost and the live burn rate is priced from tokens.
+pub fn cost_start_time(start_time: DateTime<Utc>, width: BucketWidth) -> Option<DateTime<Utc>> {
+    match width {
+        BucketWidth::Day => Some(start_time),
+        BucketWidth::Hour | BucketWidth::Minute => None,
     }
 }
 
-fn usage_start_time() -> DateTime<Utc> {
-    let now = Utc::now();
-    (now.date_naive() - Duration::days(DAYS_TO_FETCH))
-        .and_hms_opt(0, 0, 0)
-        .unwrap()
-        .and_utc()
+/// Start of the window a refresh needs to request. If the existing data
+/// doesn't reach back to `required_from` this is a full fetch from there;
+/// otherwise only the days since the last successful fetch, plus
+/// `REFETCH_TRAILING_DAYS` to catch late-arriving data.
+pub fn fetch_start_time(
+    last_updated: Option<DateTime<Utc>>,
+    covered_from: Option<DateTime<Utc>>,
+    required_from: DateTime<Utc>,
+) -> DateTime<Utc> {
+    match (last_updated, covered_from) {
+        (Some(updated), Some(covered_from)) if covered_from <= required_from => {
+            (updated.date_naive() - Duration::days(REFETCH_TRAILING_DAYS - 1))
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+                .max(covered_from)
+        }
+        _ => required_from,
+    }
+}
+
+/// Replaces everything from `since` onwards with `fresh`, keeping older buckets.
+pub fn merge_since<T>(
+    existing: &mut Vec<T>,
+    fresh: Vec<T>,
+    since: DateTime<Utc>,
+    date: impl Fn(&T) -> DateTime<Utc>,
+) {
+    existing.retain(|d| date(d) < since);
+    existing.extend(fresh);
+    existing.sort_by_key(|d| date(d));
 }
 
 fn append_error(target: &mut Option<String>, message: String) {
@@ -43,207 +81,129 @@ fn append_error(target: &mut Option<String>, message: String) {
     }
 }
 
-async fn fetch_openai_data(client: Option<OpenAIClient>) -> crate::provider::FetchOutcome {
+pub async fn fetch_data(
+    id: ProviderId,
+    provider: Arc<dyn UsageProvider>,
+    start_time: DateTime<Utc>,
+    width: BucketWidth,
+    known_api_key_ids: HashSet<String>,
+) -> FetchOutcome {
     let mut errors = ProviderErrors::default();
-    let mut cost_data = Vec::new();
-    let mut usage_data = Vec::new();
+    let mut cost_data = None;
+    let mut usage_data = None;
     let mut api_key_names = HashMap::new();
-    let start_time = usage_start_time();
-
-    if let Some(client) = client {
-        let (costs_result, usage_result) = tokio::join!(
-            client.fetch_costs(start_time),
-            client.fetch_usage(start_time)
-        );
-
-        match costs_result {
-            Ok(buckets) => {
-                for bucket in buckets {
-                    let date = timestamp_to_date(bucket.start_time);
-
-                    for result in bucket.results {
-                        cost_data.push(DailyData {
-                            date,
-                            cost: result.amount.value(),
-                            line_item: result.line_item,
-                        });
-                    }
-                }
-                cost_data.sort_by_key(|d| d.date);
-            }
-            Err(e) => {
-                append_error(&mut errors.cost, e.to_string());
-            }
+    let mut warnings = Vec::new();
+    let progress = provider.progress();
+
+    let costs = async {
+        match cost_start_time(start_time, width) {
+            Some(cost_start) => Some(provider.fetch_costs(cost_start).await),
+            None => None,
         }
+    };
+    let (costs_result, usage_result) = tokio::join!(costs, provider.fetch_usage(start_time, width));
+
+    match costs_result {
+        Some(Ok(mut costs)) => {
+            warnings.extend(costs.truncated.take());
+            costs.data.sort_by_key(|d| d.date);
+            cost_data = Some(costs);
+        }
+        Some(Err(e)) => {
+            append_error(&mut errors.cost, e.to_string());
+        }
+        None => {}
+    }
 
-        match usage_result {
-            Ok(buckets) => {
-                let mut api_key_ids = HashSet::new();
-
-                for bucket in &buckets {
-                    let date = timestamp_to_date(bucket.start_time);
-
-                    for result in &bucket.results {
-                        if result.input_tokens > 0 || result.output_tokens > 0 {
-                            usage_data.push(DailyUsageData {
-                                date,
-                                input_tokens: result.input_tokens,
-                                output_tokens: result.output_tokens,
-                                api_key_id: result.api_key_id.clone(),
-                                model: result.model.clone(),
-                                cache_read_input_tokens: None,
-                                uncached_input_tokens: None,
-                                num_requests: Some(result.num_model_requests),
-                            });
-
-                            if let Some(api_key_id) = &result.api_key_id {
-                                api_key_ids.insert(api_key_id.clone());
-                            }
-                        }
-                    }
+    match usage_result {
+        Ok(mut usage) => {
+            warnings.extend(usage.truncated.take());
+            usage.data.sort_by_key(|d| d.date);
+
+            let api_key_ids: Vec<String> = usage
+                .data
+                .iter()
+                .filter_map(|d| d.api_key_id.clone())
+                .filter(|id| !id.is_empty() && id != "unknown")
+                .filter(|id| !known_api_key_ids.contains(id))
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+
+            // The live view only shows models, so its polls skip the lookups.
+            if !api_key_ids.is_empty() && width != BucketWidth::Minute {
+                let names = provider.resolve_api_key_names(&api_key_ids);
+                match progress.track("key names", names).await {
+                    Ok(names) => api_key_names.extend(names),
+                    Err(e) => append_error(&mut errors.usage, e.to_string()),
                 }
-                usage_data.sort_by_key(|d| d.date);
-
-                let api_key_ids: Vec<String> = api_key_ids
-                    .into_iter()
-                    .filter(|id| !id.is_empty() && id != "unknown")
-                    .collect();
-
-                if !api_key_ids.is_empty() {
-                    match client.fetch_api_key_names_for_ids(&api_key_ids).await {
-                        Ok(api_key_map) => api_key_names.extend(api_key_map),
-                        Err(e) => {
-                            append_error(
-                                &mut errors.usage,
-                                format!("API key name fetch failed: {}", e),
-                            );
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                append_error(&mut errors.usage, format!("Usage fetch failed: {}", e));
             }
+            usage_data = Some(usage);
+        }
+        Err(e) => {
+            append_error(&mut errors.usage, format!("Usage fetch failed: {}", e));
         }
     }
 
-    crate::provider::FetchOutcome {
-        provider: Provider::OpenAI,
+    FetchOutcome {
+        provider: id,
+        start_time,
+        width,
+        fetched_at: Utc::now(),
         cost_data,
         usage_data,
         api_key_names,
         errors,
+        // Clones of the client share one counter, so this also picks up
+        // retries from the API key lookups.
+        retries: provider.take_retries(),
+        warnings,
     }
 }
 
-async fn fetch_anthropic_data(client: Option<AnthropicClient>) -> crate::provider::FetchOutcome {
-    let mut errors = ProviderErrors::default();
-    let mut cost_data = Vec::new();
-    let mut usage_data = Vec::new();
-    let mut api_key_names = HashMap::new();
-    let start_time = usage_start_time();
-
-    if let Some(client) = client {
-        let (costs_result, usage_result) = tokio::join!(
-            client.fetch_costs(start_time),
-            client.fetch_usage(start_time),
-        );
-
-        match costs_result {
-            Ok(buckets) => {
-                for bucket in buckets {
-                    if let Ok(bucket_start) = DateTime::parse_from_rfc3339(&bucket.starting_at) {
-                        let date = bucket_start.with_timezone(&Utc);
-                        for result in bucket.results {
-                            if let Ok(cost_cents) = result.amount.parse::<f64>() {
-                                let cost = cost_cents / CENTS_TO_DOLLARS;
-                                if cost > 0.0 {
-                                    cost_data.push(DailyData {
-                                        date,
-                                        cost,
-                                        line_item: result.model,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-                cost_data.sort_by_key(|d| d.date);
-            }
-            Err(e) => {
-                append_error(&mut errors.cost, e.to_string());
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
 
-        match usage_result {
-            Ok(buckets) => {
-                let mut api_key_ids = HashSet::new();
-
-                for bucket in &buckets {
-                    if let Ok(bucket_start) = DateTime::parse_from_rfc3339(&bucket.starting_at) {
-                        let date = bucket_start.with_timezone(&Utc);
-                        for result in &bucket.results {
-                            let input_tokens = result.uncached_input_tokens
-                                + result.cache_creation.ephemeral_1h_input_tokens
-                                + result.cache_creation.ephemeral_5m_input_tokens
-                                + result.cache_read_input_tokens;
-
-                            if input_tokens > 0 || result.output_tokens > 0 {
-                                usage_data.push(DailyUsageData {
-                                    date,
-                                    input_tokens,
-                                    output_tokens: result.output_tokens,
-                                    api_key_id: result.api_key_id.clone(),
-                                    model: result.model.clone(),
-                                    cache_read_input_tokens: Some(result.cache_read_input_tokens),
-                                    uncached_input_tokens: Some(result.uncached_input_tokens),
-                                    num_requests: None,
-                                });
-
-                                if let Some(api_key_id) = &result.api_key_id {
-                                    api_key_ids.insert(api_key_id.clone());
-                                }
-                            }
-                        }
-                    }
-                }
-                usage_data.sort_by_key(|d| d.date);
-
-                let api_key_ids: Vec<String> = api_key_ids
-                    .into_iter()
-                    .filter(|id| !id.is_empty() && id != "unknown")
-                    .collect();
-
-                if !api_key_ids.is_empty() {
-                    let name_futures: Vec<_> = api_key_ids
-                        .into_iter()
-                        .map(|api_key_id| {
-                            let client_clone = client.clone();
-                            tokio::spawn(async move {
-                                let result = client_clone.fetch_api_key_name(&api_key_id).await;
-                                (api_key_id, result)
-                            })
-                        })
-                        .collect();
-
-                    for handle in name_futures {
-                        if let Ok((api_key_id, Ok(name))) = handle.await {
-                            api_key_names.insert(api_key_id, name);
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                append_error(&mut errors.usage, format!("Usage fetch failed: {}", e));
-            }
-        }
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap()
     }
 
-    crate::provider::FetchOutcome {
-        provider: Provider::Anthropic,
-        cost_data,
-        usage_data,
-        api_key_names,
-        errors,
+    #[test]
+    fn first_fetch_starts_at_required_from() {
+        assert_eq!(fetch_start_time(None, None, at(1, 0)), at(1, 0));
+        assert_eq!(fetch_start_time(Some(at(20, 9)), None, at(1, 0)), at(1, 0));
+    }
+
+    #[test]
+    fn longer_range_refetches_in_full() {
+        let start = fetch_start_time(Some(at(20, 9)), Some(at(10, 0)), at(1, 0));
+        assert_eq!(start, at(1, 0));
+    }
+
+    #[test]
+    fn refresh_refetches_trailing_days() {
+        // Covering exactly `required_from` is enough for an incremental fetch.
+        let start = fetch_start_time(Some(at(20, 9)), Some(at(1, 0)), at(1, 0));
+        assert_eq!(start, at(19, 0));
+
+        // ...but it never reaches back before the data that's there.
+        let start = fetch_start_time(Some(at(20, 9)), Some(at(20, 0)), at(25, 0));
+        assert_eq!(start, at(20, 0));
+    }
+
+    #[test]
+    fn merge_replaces_rows_from_since() {
+        let mut existing = vec![at(1, 0), at(2, 0), at(3, 0)];
+        merge_since(&mut existing, vec![at(4, 0), at(2, 0)], at(2, 0), |d| *d);
+        assert_eq!(existing, vec![at(1, 0), at(2, 0), at(4, 0)]);
+    }
+
+    #[test]
+    fn merge_with_empty_fetch_drops_window() {
+        let mut existing = vec![at(1, 0), at(2, 0), at(3, 0)];
+        merge_since(&mut existing, Vec::new(), at(2, 0), |d| *d);
+        assert_eq!(existing, vec![at(1, 0)]);
     }
 }
@@ -0,0 +1,145 @@
+use crate::models::DailyData;
+use crate::ui::content::shared;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use std::collections::BTreeMap;
+
+/// Complete days the trend is fitted to.
+const HISTORY_DAYS: i64 = 28;
+/// Fewer complete days than this and there's no trend to speak of.
+const MIN_DAYS: usize = 7;
+/// Each day counts half as much as the one a week later.
+const HALF_LIFE_DAYS: f64 = 7.0;
+/// Width of the band in standard deviations, about 80% for normal noise.
+const BAND_Z: f64 = 1.28;
+
+/// Projected spend up to the end of the month `today` falls in.
+pub struct Forecast {
+    /// Expected total at the end of the month.
+    pub projected: f64,
+    /// Never below what's already spent.
+    pub low: f64,
+    pub high: f64,
+    /// Expected cost of each day after today until the end of the month.
+    pub remaining: Vec<(NaiveDate, f64)>,
+}
+
+impl Forecast {
+    /// Fits a weighted linear trend to the complete days before `today`,
+    /// recent days weighing more, and extends it to the end of the month.
+    /// `daily` has one entry per day the API reported, zero included.
+    pub fn new(daily: &BTreeMap<NaiveDate, f64>, today: NaiveDate) -> Option<Self> {
+        let history: Vec<(f64, f64)> = daily
+            .range(today - Duration::days(HISTORY_DAYS)..today)
+            .map(|(date, cost)| ((*date - today).num_days() as f64, *cost))
+            .collect();
+        if history.len() < MIN_DAYS {
+            return None;
+        }
+
+        let weight = |x: f64| 0.5f64.powf(-x / HALF_LIFE_DAYS);
+        let total_weight: f64 = history.iter().map(|(x, _)| weight(*x)).sum();
+        let mean = |f: &dyn Fn(f64, f64) -> f64| {
+            history.iter().map(|(x, y)| weight(*x) * f(*x, *y)).sum::<f64>() / total_weight
+        };
+        let mean_x = mean(&|x, _| x);
+        let mean_y = mean(&|_, y| y);
+        let var_x = mean(&|x, _| (x - mean_x).powi(2));
+        let slope = if var_x > 0.0 {
+            mean(&|x, y| (x - mean_x) * (y - mean_y)) / var_x
+        } else {
+            0.0
+        };
+        let intercept = mean_y - slope * mean_x;
+        let predict = |x: f64| (intercept + slope * x).max(0.0);
+        let residual_sd = mean(&|x, y| (y - (intercept + slope * x)).powi(2)).sqrt();
+
+        let month_start = today.with_day(1).unwrap();
+        let month_end = (month_start + Duration::days(31)).with_day(1).unwrap() - Duration::days(1);
+        let spent: f64 = daily.range(month_start..=today).map(|(_, cost)| cost).sum();
+        // Today is still running, so it's expected to end at least where the
+        // trend puts it.
+        let today_cost = daily.get(&today).copied().unwrap_or(0.0);
+        let today_rest = (predict(0.0) - today_cost).max(0.0);
+        let remaining: Vec<(NaiveDate, f64)> = (1..=(month_end - today).num_days())
+            .map(|x| (today + Duration::days(x), predict(x as f64)))
+            .collect();
+
+        let projected = spent + today_rest + remaining.iter().map(|(_, cost)| cost).sum::<f64>();
+        // Day-to-day noise adds up over the days left, today included.
+        let band = BAND_Z * residual_sd * ((remaining.len() + 1) as f64).sqrt();
+        Some(Self {
+            projected,
+            low: (projected - band).max(spent),
+            high: projected + band,
+            remaining,
+        })
+    }
+}
+
+/// Daily cost of the line item `item`, or of every line item when `None`,
+/// with a zero for each reported day that had no spend.
+pub fn daily_costs(
+    data: &[DailyData],
+    buckets: &[DateTime<Utc>],
+    item: Option<&str>,
+) -> BTreeMap<NaiveDate, f64> {
+    let mut daily: BTreeMap<NaiveDate, f64> = buckets
+        .iter()
+        .map(|date| (date.date_naive(), 0.0))
+        .collect();
+    for d in data {
+        if item.is_none_or(|item| shared::extract_trimmed_string(&d.line_item) == Some(item)) {
+            *daily.entry(d.date.date_naive()).or_insert(0.0) += d.cost;
+        }
+    }
+    daily
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn flat(days: std::ops::Range<u32>, cost: f64) -> BTreeMap<NaiveDate, f64> {
+        days.map(|day| (date(day), cost)).collect()
+    }
+
+    #[test]
+    fn too_little_history() {
+        assert!(Forecast::new(&BTreeMap::new(), date(15)).is_none());
+        assert!(Forecast::new(&flat(14..15, 10.0), date(15)).is_none());
+        assert!(Forecast::new(&flat(13..15, 10.0), date(15)).is_none());
+        assert!(Forecast::new(&flat(15 - MIN_DAYS as u32..15, 10.0), date(15)).is_some());
+
+        // Today is still running, so it isn't history.
+        assert!(Forecast::new(&flat(9..16, 10.0), date(15)).is_none());
+    }
+
+    #[test]
+    fn flat_spend_projects_flat() {
+        let mut daily = flat(5..15, 10.0);
+        daily.insert(date(15), 4.0);
+        let forecast = Forecast::new(&daily, date(15)).unwrap();
+
+        // 104 spent, 6 more today and 10 on each of the 16 days left.
+        assert!((forecast.projected - 270.0).abs() < 1e-6);
+        assert!((forecast.high - forecast.low).abs() < 1e-6);
+        assert_eq!(forecast.remaining.len(), 16);
+        assert_eq!(forecast.remaining.last().unwrap().0, date(31));
+    }
+
+    #[test]
+    fn projection_never_below_spent() {
+        let mut daily: BTreeMap<NaiveDate, f64> = (1..15)
+            .map(|day| (date(day), 100.0 - 7.0 * day as f64))
+            .collect();
+        daily.insert(date(15), 0.0);
+        let spent: f64 = daily.values().sum();
+        let forecast = Forecast::new(&daily, date(15)).unwrap();
+        assert!(forecast.remaining.iter().all(|(_, cost)| *cost >= 0.0));
+        assert!(forecast.low >= spent);
+    }
+}
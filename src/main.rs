@@ -1,29 +1,115 @@
+mod anomaly;
 mod api;
 mod app;
+mod budget;
+mod cache;
+mod config;
+mod digest;
 mod events;
+mod export;
 mod fetch;
+mod forecast;
 mod models;
+mod notify;
+mod pricing;
 mod provider;
+mod reconcile;
+mod serve;
 mod ui;
 
-use app::App;
-use clap::Parser;
+use app::{App, AutoRefresh, View};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use config::Config;
 use crossterm::{
     event::{self, Event, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use models::BucketWidth;
+use provider::{ProviderId, UsageProvider};
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::{io, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::HashSet,
+    io,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::sync::Mutex;
 
 const EVENT_POLL_TIMEOUT_MS: u64 = 50;
+// Matches the 1-minute buckets the live view requests.
+const LIVE_POLL_INTERVAL: Duration = Duration::from_secs(60);
+const AUTO_REFRESH_TICK: Duration = Duration::from_secs(1);
 
 #[derive(Parser)]
 #[command(about = "A terminal-based LLM cost and usage monitor")]
 struct Args {
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     env_file: Option<PathBuf>,
+
+    /// Path to the config file (defaults to ~/.config/toktop/config.toml)
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Send a provider's requests to a different API root, e.g. openai=http://localhost:8080
+    #[arg(
+        long = "base-url",
+        value_name = "PROVIDER=URL",
+        value_parser = parse_base_url,
+        global = true
+    )]
+    base_urls: Vec<(String, String)>,
+
+    /// Refresh automatically every interval, e.g. 90s, 5m or 1h (0 or off to disable)
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_refresh_interval)]
+    refresh_interval: Option<Duration>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print cost or usage as CSV, JSON or NDJSON instead of starting the UI
+    Export(export::ExportArgs),
+    /// Fetch today's cost and usage periodically and serve them as Prometheus metrics
+    Serve(serve::ServeArgs),
+    /// Send yesterday's digest to the notification channels
+    Digest(digest::DigestArgs),
+}
+
+fn parse_refresh_interval(s: &str) -> Result<Duration, String> {
+    config::parse_interval(s).map_err(|e| e.to_string())
+}
+
+fn parse_base_url(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((provider, url)) if !provider.is_empty() && !url.is_empty() => {
+            Ok((provider.to_lowercase(), url.to_string()))
+        }
+        _ => Err(format!("expected PROVIDER=URL, got '{}'", s)),
+    }
+}
+
+fn load_config(path: Option<PathBuf>) -> Config {
+    let (path, explicit) = match path {
+        Some(path) => (path, true),
+        None => match Config::default_path() {
+            Some(path) => (path, false),
+            None => return Config::default(),
+        },
+    };
+
+    if !explicit && !path.exists() {
+        return Config::default();
+    }
+
+    Config::load(&path).unwrap_or_else(|e| {
+        eprintln!("Warning: {:#}", e);
+        Config::default()
+    })
 }
 
 #[tokio::main]
@@ -40,33 +126,109 @@ async fn main() -> io::Result<()> {
         }
     }
 
+    let mut config = load_config(args.config);
+    for (provider_id, base_url) in &args.base_urls {
+        config.override_base_url(provider_id, base_url.clone());
+    }
+    let refresh_interval = args.refresh_interval.or_else(|| {
+        let interval = config.refresh_interval.as_deref()?;
+        config::parse_interval(interval)
+            .map_err(|e| eprintln!("Warning: {:#}", e))
+            .ok()
+    });
+    let budgets: Vec<_> = config
+        .budgets
+        .iter()
+        .filter(|budget| {
+            budget
+                .validate()
+                .map_err(|e| eprintln!("Warning: {:#}", e))
+                .is_ok()
+        })
+        .cloned()
+        .collect();
+    let pricing = pricing::Pricing::new(&config.pricing);
+    let accounts = provider::accounts(&config);
+    for (provider_id, _) in &args.base_urls {
+        if !accounts.iter().any(|a| a.provider.id() == provider_id) {
+            eprintln!("Warning: Unknown provider '{}' in --base-url", provider_id);
+        }
+    }
+
+    if let Some(command) = args.command {
+        let result = match command {
+            Command::Export(export_args) => export::run(accounts, pricing, export_args).await,
+            Command::Serve(serve_args) => {
+                serve::run(accounts, budgets, config.notifications, serve_args).await
+            }
+            Command::Digest(digest_args) => {
+                digest::run(accounts, config.notifications, digest_args).await
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     enable_raw_mode()?;
     execute!(io::stdout(), EnterAlternateScreen)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
 
-    let mut app = App::new();
-    if let Ok(key) = std::env::var("OPENAI_ADMIN_KEY") {
-        app.set_openai_client(key);
+    let mut app = App::new(accounts);
+    app.budgets = budgets;
+    app.pricing = pricing;
+    if let Some(interval) = refresh_interval.filter(|interval| !interval.is_zero()) {
+        if let Ok(interval) = chrono::Duration::from_std(interval) {
+            app.auto_refresh = Some(AutoRefresh::new(interval));
+        }
     }
-    if let Ok(key) = std::env::var("ANTHROPIC_ADMIN_KEY") {
-        app.set_anthropic_client(key);
+    for id in app.provider_ids() {
+        if let Ok(key) = std::env::var(&app.provider_info(id).env_var) {
+            app.set_api_key(id, key);
+        }
+        let info = app.provider_info(id);
+        if let Some(key_hash) = info.key_hash {
+            if let Some(entry) = cache::load(&info.cache_key(), key_hash) {
+                app.restore_cache(id, entry);
+            }
+        }
     }
 
     let app = Arc::new(Mutex::new(app));
 
-    spawn_fetch_task(app.clone());
+    spawn_fetch_task(app.clone(), false);
+    spawn_auto_refresh_task(app.clone());
+    let mut last_live_poll: Option<Instant> = None;
 
     loop {
         {
             let mut app_lock = app.lock().await;
             let current_provider = app_lock.current_provider();
-            if !app_lock.has_client(current_provider) && app_lock.api_key_popup_active.is_none() {
+            if app_lock.aggregate.is_none()
+                && !app_lock.has_client(current_provider)
+                && app_lock.api_key_popup_active.is_none()
+            {
                 app_lock.show_api_key_popup(current_provider);
             }
             app_lock.update_animation_frame();
             terminal.draw(|f| ui::render(f, &mut app_lock))?;
         }
 
+        let live_poll_due = {
+            let app_lock = app.lock().await;
+            app_lock.current_view == View::Live
+                && app_lock.aggregate.is_none()
+                && app_lock.has_client(app_lock.current_provider())
+                && !app_lock.is_loading(app_lock.current_provider())
+                && last_live_poll.is_none_or(|polled| polled.elapsed() >= LIVE_POLL_INTERVAL)
+        };
+        if live_poll_due {
+            last_live_poll = Some(Instant::now());
+            spawn_fetch_task(app.clone(), false);
+        }
+
         if event::poll(Duration::from_millis(EVENT_POLL_TIMEOUT_MS))? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
@@ -76,7 +238,7 @@ async fn main() -> io::Result<()> {
                     };
 
                     match action {
-                        events::EventAction::Refresh => spawn_fetch_task(app.clone()),
+                        events::EventAction::Refresh => spawn_fetch_task(app.clone(), true),
                         events::EventAction::Quit => break,
                         events::EventAction::None => {}
                     }
@@ -91,24 +253,94 @@ async fn main() -> io::Result<()> {
     Ok(())
 }
 
-fn spawn_fetch_task(app: Arc<Mutex<App>>) {
+/// Checks the auto-refresh timer once a second. The schedule itself lives in
+/// `App` so manual refreshes and failures move it too.
+fn spawn_auto_refresh_task(app: Arc<Mutex<App>>) {
     tokio::spawn(async move {
-        let (provider, openai_client, anthropic_client) = {
-            let mut app_lock = app.lock().await;
-
-            if app_lock.loading {
-                return;
+        loop {
+            tokio::time::sleep(AUTO_REFRESH_TICK).await;
+            let due = app.lock().await.auto_refresh_due();
+            if due {
+                spawn_fetch_task(app.clone(), false);
             }
+        }
+    });
+}
 
-            let provider = app_lock.current_provider();
-            app_lock.start_fetch();
-            let (openai_client, anthropic_client) = app_lock.get_clients();
-            (provider, openai_client, anthropic_client)
+/// Refreshes every provider that has a client (only the ones on screen for a
+/// live poll), each in its own task so a slow provider doesn't hold up the
+/// others. A fetch that's already running is restarted when `supersede` is
+/// set (the user asked for this refresh, e.g. for a new range) and left alone
+/// otherwise.
+fn spawn_fetch_task(app: Arc<Mutex<App>>, supersede: bool) {
+    tokio::spawn(async move {
+        let mut app_lock = app.lock().await;
+        // Live polls only need the accounts on screen.
+        let ids = if app_lock.fetch_width() == BucketWidth::Minute {
+            app_lock.scope_providers(app_lock.scope())
+        } else {
+            app_lock.provider_ids()
         };
+        for id in ids {
+            if !app_lock.has_client(id) || (app_lock.is_loading(id) && !supersede) {
+                continue;
+            }
+            let generation = app_lock.start_fetch(id);
+            let (start_time, width, known_api_key_ids) = app_lock.fetch_window(id);
+            let provider = app_lock.provider_handle(id);
+            let cache_key = app_lock.provider_info(id).cache_key();
+            let request = FetchRequest {
+                id,
+                generation,
+                provider,
+                cache_key,
+                start_time,
+                width,
+                known_api_key_ids,
+            };
+            let task = tokio::spawn(fetch_provider(app.clone(), request));
+            app_lock.track_fetch(id, task.abort_handle());
+        }
+    });
+}
 
-        let outcome = fetch::fetch_data(provider, openai_client, anthropic_client).await;
+/// Everything a provider's fetch task needs, captured while the app is locked.
+struct FetchRequest {
+    id: ProviderId,
+    /// Lets `finish_fetch` drop the result if a newer fetch has started.
+    generation: u64,
+    provider: Arc<dyn UsageProvider>,
+    cache_key: String,
+    start_time: DateTime<Utc>,
+    width: BucketWidth,
+    known_api_key_ids: HashSet<String>,
+}
+
+async fn fetch_provider(app: Arc<Mutex<App>>, request: FetchRequest) {
+    let FetchRequest {
+        id,
+        generation,
+        provider,
+        cache_key,
+        start_time,
+        width,
+        known_api_key_ids,
+    } = request;
+    let outcome = fetch::fetch_data(id, provider, start_time, width, known_api_key_ids).await;
 
+    let cache_entry = {
         let mut app_lock = app.lock().await;
-        app_lock.finish_fetch(outcome);
-    });
+        app_lock.finish_fetch(outcome, generation);
+        // Live polls don't change what's cached, so they don't rewrite it.
+        if width == BucketWidth::Minute {
+            None
+        } else {
+            app_lock.cache_entry(id)
+        }
+    };
+
+    // Only complete fetches are cached; a failed refresh keeps the previous cache.
+    if let Some(entry) = cache_entry {
+        let _ = cache::save(&cache_key, &entry);
+    }
 }
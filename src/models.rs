@@ -1,5 +1,63 @@
-use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Width of the buckets a report is requested in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BucketWidth {
+    Day,
+    Hour,
+    Minute,
+}
+
+impl BucketWidth {
+    /// Value of the `bucket_width` query parameter.
+    pub fn param(self) -> &'static str {
+        match self {
+            BucketWidth::Day => "1d",
+            BucketWidth::Hour => "1h",
+            BucketWidth::Minute => "1m",
+        }
+    }
+
+    pub fn duration(self) -> Duration {
+        match self {
+            BucketWidth::Day => Duration::days(1),
+            BucketWidth::Hour => Duration::hours(1),
+            BucketWidth::Minute => Duration::minutes(1),
+        }
+    }
+
+    /// Start of the bucket `date` falls in.
+    pub fn truncate(self, date: DateTime<Utc>) -> DateTime<Utc> {
+        date.duration_trunc(self.duration()).unwrap_or(date)
+    }
+
+    /// Format of the chart axis label for a bucket.
+    pub fn label_format(self) -> &'static str {
+        match self {
+            BucketWidth::Day => "%m/%d",
+            BucketWidth::Hour => "%m/%d %H:00",
+            BucketWidth::Minute => "%H:%M",
+        }
+    }
+
+    /// Chart title prefix, e.g. "Daily Token Usage".
+    pub fn label(self) -> &'static str {
+        match self {
+            BucketWidth::Day => "Daily",
+            BucketWidth::Hour => "Hourly",
+            BucketWidth::Minute => "Per-minute",
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            BucketWidth::Day => "day",
+            BucketWidth::Hour => "hour",
+            BucketWidth::Minute => "minute",
+        }
+    }
+}
 
 #[derive(Deserialize)]
 pub struct AnthropicApiKeyResponse {
@@ -15,25 +73,63 @@ pub struct AnthropicApiKeyResponse {
     pub created_at: Option<String>,
 }
 
-#[derive(Clone)]
+/// Normalized rows of a cost or usage report, plus the start of every bucket
+/// the API returned. A bucket with no rows means nothing was spent that day,
+/// while a day without a bucket means the API had no data for it.
+pub struct Report<T> {
+    pub bucket_dates: Vec<DateTime<Utc>>,
+    pub data: Vec<T>,
+    /// Why the report is missing later buckets, when the API's pages
+    /// couldn't all be followed.
+    pub truncated: Option<String>,
+}
+
+impl<T> Report<T> {
+    /// OpenAI reports usage per endpoint, so the same bucket can show up
+    /// more than once; dates are sorted and deduplicated here.
+    pub fn new(mut bucket_dates: Vec<DateTime<Utc>>, data: Vec<T>) -> Self {
+        bucket_dates.sort();
+        bucket_dates.dedup();
+        Self {
+            bucket_dates,
+            data,
+            truncated: None,
+        }
+    }
+
+    pub fn with_truncation(mut self, truncated: Option<String>) -> Self {
+        self.truncated = truncated;
+        self
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DailyData {
     pub date: DateTime<Utc>,
     pub cost: f64,
     pub line_item: Option<String>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DailyUsageData {
     pub date: DateTime<Utc>,
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub api_key_id: Option<String>,
     pub model: Option<String>,
-    // Cache metrics (Anthropic only)
+    // Cache metrics, part of `input_tokens`
     pub cache_read_input_tokens: Option<u64>,
     pub uncached_input_tokens: Option<u64>,
+    // Cache writes by lifetime (Anthropic only), part of `input_tokens`
+    #[serde(default)]
+    pub cache_write_5m_tokens: Option<u64>,
+    #[serde(default)]
+    pub cache_write_1h_tokens: Option<u64>,
     // Request count (OpenAI only)
     pub num_requests: Option<u64>,
+    /// Sent through the batch API, which is billed at a discount.
+    #[serde(default)]
+    pub batch: bool,
 }
 
 #[derive(Deserialize)]
@@ -177,7 +273,6 @@ pub struct AnthropicUsageItem {
     #[serde(default)]
     pub model: Option<String>,
     #[serde(default)]
-    #[expect(unused)]
     pub service_tier: Option<String>,
     #[serde(default)]
     #[expect(unused)]
@@ -234,7 +329,6 @@ pub struct OpenAIUsageResult {
     #[serde(default)]
     pub output_tokens: u64,
     #[serde(default)]
-    #[expect(unused)]
     pub input_cached_tokens: u64,
     #[serde(default)]
     #[expect(unused)]
@@ -257,6 +351,8 @@ pub struct OpenAIUsageResult {
     pub user_id: Option<String>,
     #[serde(default)]
     pub api_key_id: Option<String>,
+    #[serde(default)]
+    pub batch: Option<bool>,
 }
 
 #[derive(Deserialize)]
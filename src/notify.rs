@@ -0,0 +1,226 @@
+use crate::api;
+use crate::budget::{BudgetStatus, Period};
+use crate::digest::Digest;
+use crate::ui::utils::format_budget_amount;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Environment variable the SMTP password is read from, so it doesn't have
+/// to live in the config file.
+pub const SMTP_PASSWORD_VAR: &str = "TOKTOP_SMTP_PASSWORD";
+
+/// Where `toktop serve` and `toktop digest` send notifications.
+///
+/// ```toml
+/// [notifications]
+/// digest_hour = 8
+///
+/// [[notifications.channels]]
+/// type = "slack"
+/// url = "https://hooks.slack.com/services/..."
+/// ```
+#[derive(Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// Hour (UTC) from which `serve` sends the digest of the day before.
+    /// No digest is sent when unset.
+    #[serde(default)]
+    pub digest_hour: Option<u32>,
+    #[serde(default)]
+    pub channels: Vec<Channel>,
+}
+
+impl NotifyConfig {
+    pub fn validate(&self) -> Result<()> {
+        match self.digest_hour {
+            Some(hour) if hour > 23 => bail!("digest_hour must be 0 to 23, got {}", hour),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Channel {
+    /// Posts the notification as JSON.
+    Webhook {
+        url: String,
+    },
+    /// Posts a Slack incoming-webhook message.
+    Slack {
+        url: String,
+    },
+    Email(EmailConfig),
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    pub host: String,
+    /// Defaults to 25 without TLS, 587 for STARTTLS and 465 for TLS.
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: TlsMode,
+    /// Logs in with the password from `$TOKTOP_SMTP_PASSWORD` when set.
+    #[serde(default)]
+    pub username: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TlsMode {
+    /// Plain SMTP, for local relays and test servers.
+    None,
+    #[default]
+    Starttls,
+    Tls,
+}
+
+/// A budget that was exceeded, sent once per budget and period.
+#[derive(Serialize)]
+pub struct BudgetAlert {
+    pub budget: String,
+    pub period: Period,
+    pub period_start: NaiveDate,
+    pub spent: f64,
+    pub limit: f64,
+    /// `usd` or `tokens`.
+    pub unit: &'static str,
+    pub percent: f64,
+    #[serde(skip)]
+    amount: String,
+}
+
+impl BudgetAlert {
+    pub fn new(status: &BudgetStatus, today: NaiveDate) -> Self {
+        Self {
+            budget: status.budget.label(),
+            period: status.budget.period,
+            period_start: status.budget.period.start(today).date_naive(),
+            spent: status.spent,
+            limit: status.budget.limit(),
+            unit: if status.budget.tokens.is_some() {
+                "tokens"
+            } else {
+                "usd"
+            },
+            percent: status.percent(),
+            amount: format_budget_amount(status),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notification {
+    BudgetExceeded(BudgetAlert),
+    Digest(Digest),
+}
+
+impl Notification {
+    pub fn title(&self) -> String {
+        match self {
+            Notification::BudgetExceeded(alert) => format!("Budget exceeded: {}", alert.budget),
+            Notification::Digest(digest) => format!("toktop digest for {}", digest.date),
+        }
+    }
+
+    pub fn text(&self) -> String {
+        match self {
+            Notification::BudgetExceeded(alert) => format!(
+                "{} ({:.0}%) spent since {}.",
+                alert.amount, alert.percent, alert.period_start
+            ),
+            Notification::Digest(digest) => digest.text(),
+        }
+    }
+}
+
+impl Channel {
+    pub async fn send(&self, notification: &Notification) -> Result<()> {
+        match self {
+            Channel::Webhook { url } => {
+                let mut body = serde_json::to_value(notification)?;
+                body["title"] = notification.title().into();
+                body["text"] = notification.text().into();
+                post(url, &body).await
+            }
+            Channel::Slack { url } => {
+                let text = format!("*{}*\n{}", notification.title(), notification.text());
+                post(url, &json!({ "text": text })).await
+            }
+            Channel::Email(email) => send_email(email, notification).await,
+        }
+    }
+}
+
+/// Sends `notification` to every channel, trying all of them before
+/// reporting the ones that failed.
+pub async fn send(channels: &[Channel], notification: &Notification) -> Result<()> {
+    let mut errors = Vec::new();
+    for channel in channels {
+        if let Err(e) = channel.send(notification).await {
+            errors.push(format!("{:#}", e));
+        }
+    }
+    if !errors.is_empty() {
+        bail!("Failed to send notification: {}", errors.join("; "));
+    }
+    Ok(())
+}
+
+/// Posts once, without the API client's retries: a server error doesn't
+/// mean the message wasn't delivered, and posting it again could repeat it.
+async fn post(url: &str, body: &serde_json::Value) -> Result<()> {
+    let response = api::http_client()
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .with_context(|| format!("Failed to post to {}", url))?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        bail!("{} returned {}: {}", url, status, text);
+    }
+    Ok(())
+}
+
+async fn send_email(config: &EmailConfig, notification: &Notification) -> Result<()> {
+    let mut transport = match config.tls {
+        TlsMode::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        TlsMode::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+        TlsMode::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+    };
+    if let Some(port) = config.port {
+        transport = transport.port(port);
+    }
+    if let Some(username) = &config.username {
+        let password = std::env::var(SMTP_PASSWORD_VAR)
+            .with_context(|| format!("{} is not set", SMTP_PASSWORD_VAR))?;
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+
+    let mut message = lettre::Message::builder()
+        .from(config.from.parse().context("Invalid from address")?)
+        .subject(notification.title());
+    for to in &config.to {
+        message = message.to(to
+            .parse()
+            .with_context(|| format!("Invalid address '{}'", to))?);
+    }
+    let message = message.body(notification.text())?;
+    transport
+        .build()
+        .send(message)
+        .await
+        .with_context(|| format!("Failed to send email through {}", config.host))?;
+    Ok(())
+}
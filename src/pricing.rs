@@ -0,0 +1,252 @@
+use crate::models::DailyUsageData;
+use crate::ui::content::shared;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// List prices shipped with toktop, see the comment at the top of the file.
+const BUILT_IN: &str = include_str!("pricing.toml");
+/// Both providers bill batch requests at half the regular rate.
+const BATCH_DISCOUNT: f64 = 0.5;
+
+/// Dollars per million tokens of one model.
+///
+/// ```toml
+/// [pricing."gpt-4o"]
+/// input = 2.50
+/// output = 10.00
+/// cache_read = 1.25
+///
+/// [pricing."claude-sonnet-4"]
+/// input = 3.00
+/// output = 15.00
+/// cache_read = 0.30
+/// cache_write_5m = 3.75
+/// cache_write_1h = 6.00
+/// batch_input = 1.50
+/// batch_output = 7.50
+/// ```
+#[derive(Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPrice {
+    /// Uncached input.
+    pub input: f64,
+    pub output: f64,
+    /// Defaults to `input`.
+    #[serde(default)]
+    pub cache_read: Option<f64>,
+    /// Defaults to `input`.
+    #[serde(default)]
+    pub cache_write_5m: Option<f64>,
+    /// Defaults to `input`.
+    #[serde(default)]
+    pub cache_write_1h: Option<f64>,
+    /// Uncached input sent through the batch API; half of `input` by default.
+    #[serde(default)]
+    pub batch_input: Option<f64>,
+    /// Half of `output` by default.
+    #[serde(default)]
+    pub batch_output: Option<f64>,
+}
+
+impl ModelPrice {
+    /// Dollars the tokens of `d` cost at these rates. Cache reads and writes
+    /// are priced the same with or without batch.
+    pub fn cost(&self, d: &DailyUsageData) -> f64 {
+        let cache_read = d.cache_read_input_tokens.unwrap_or(0);
+        let write_5m = d.cache_write_5m_tokens.unwrap_or(0);
+        let write_1h = d.cache_write_1h_tokens.unwrap_or(0);
+        let uncached = d.uncached_input_tokens.unwrap_or_else(|| {
+            d.input_tokens
+                .saturating_sub(cache_read + write_5m + write_1h)
+        });
+        let (input, output) = if d.batch {
+            (
+                self.batch_input.unwrap_or(self.input * BATCH_DISCOUNT),
+                self.batch_output.unwrap_or(self.output * BATCH_DISCOUNT),
+            )
+        } else {
+            (self.input, self.output)
+        };
+        let dollars = uncached as f64 * input
+            + cache_read as f64 * self.cache_read.unwrap_or(self.input)
+            + write_5m as f64 * self.cache_write_5m.unwrap_or(self.input)
+            + write_1h as f64 * self.cache_write_1h.unwrap_or(self.input)
+            + d.output_tokens as f64 * output;
+        dollars / 1_000_000.0
+    }
+}
+
+/// The built-in prices with the config file's `[pricing]` tables on top.
+pub struct Pricing {
+    models: HashMap<String, ModelPrice>,
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        Self::new(&HashMap::new())
+    }
+}
+
+impl Pricing {
+    pub fn new(overrides: &HashMap<String, ModelPrice>) -> Self {
+        let mut models: HashMap<String, ModelPrice> =
+            toml::from_str(BUILT_IN).expect("built-in pricing table is valid");
+        models.extend(
+            overrides
+                .iter()
+                .map(|(model, price)| (model.trim().to_lowercase(), *price)),
+        );
+        Self { models }
+    }
+
+    /// Price of the key `model` is named after: the key itself, or the key
+    /// followed by a snapshot date or `-latest`, like `gpt-4o-2024-08-06` or
+    /// `claude-sonnet-4-20250514`. Anything else after the key names another
+    /// model (`o1-pro` is not `o1`) and needs a key of its own.
+    pub fn price(&self, model: &str) -> Option<&ModelPrice> {
+        let model = model.trim().to_lowercase();
+        if let Some(price) = self.models.get(&model) {
+            return Some(price);
+        }
+        self.models
+            .iter()
+            .filter(|(key, _)| {
+                model
+                    .strip_prefix(key.as_str())
+                    .is_some_and(is_snapshot_suffix)
+            })
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, price)| price)
+    }
+
+    /// Estimated cost of `d`, or `None` if its model has no price.
+    pub fn estimate(&self, d: &DailyUsageData) -> Option<f64> {
+        let model = shared::extract_trimmed_string(&d.model)?;
+        Some(self.price(model)?.cost(d))
+    }
+}
+
+/// Whether `rest` marks a snapshot of a model rather than another model:
+/// `-latest`, or a date as `-YYYY-MM-DD` (OpenAI), `-YYYYMMDD` (Anthropic) or
+/// `-MMDD` (older OpenAI models).
+fn is_snapshot_suffix(rest: &str) -> bool {
+    let Some(rest) = rest.strip_prefix('-') else {
+        return false;
+    };
+    if rest == "latest" {
+        return true;
+    }
+    let parts: Vec<&str> = rest.split('-').collect();
+    let lengths: Vec<usize> = parts.iter().map(|part| part.len()).collect();
+    parts
+        .iter()
+        .all(|part| part.bytes().all(|b| b.is_ascii_digit()))
+        && matches!(lengths.as_slice(), [4, 2, 2] | [8] | [4])
+}
+
+/// Token-derived cost of each series of the usage chart.
+#[derive(Default)]
+pub struct CostEstimate {
+    pub by_series: HashMap<String, f64>,
+    /// Tokens of models without a price, left out of `by_series`.
+    pub unpriced_tokens: HashMap<String, u64>,
+}
+
+impl CostEstimate {
+    /// Estimated cost of one series, or of all of them for `None`, and
+    /// whether any of its tokens had no price.
+    pub fn total(&self, series: Option<&str>) -> (f64, bool) {
+        match series {
+            Some(series) => (
+                self.by_series.get(series).copied().unwrap_or(0.0),
+                self.unpriced_tokens.contains_key(series),
+            ),
+            None => (
+                self.by_series.values().sum(),
+                !self.unpriced_tokens.is_empty(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `model` gets the price listed under `key`.
+    fn priced_as(pricing: &Pricing, model: &str, key: &str) -> bool {
+        match (pricing.price(model), pricing.models.get(key)) {
+            (Some(price), Some(expected)) => std::ptr::eq(price, expected),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn exact_names() {
+        let pricing = Pricing::default();
+        assert!(priced_as(&pricing, "gpt-4o", "gpt-4o"));
+        assert!(priced_as(&pricing, " GPT-4o-Mini ", "gpt-4o-mini"));
+        assert!(priced_as(&pricing, "o1-pro", "o1-pro"));
+    }
+
+    #[test]
+    fn dated_snapshots() {
+        let pricing = Pricing::default();
+        for (model, key) in [
+            ("gpt-4o-2024-08-06", "gpt-4o"),
+            ("gpt-4o-mini-2024-07-18", "gpt-4o-mini"),
+            ("gpt-3.5-turbo-0125", "gpt-3.5-turbo"),
+            ("claude-sonnet-4-20250514", "claude-sonnet-4"),
+            ("claude-sonnet-4-5-20250929", "claude-sonnet-4-5"),
+            ("claude-3-5-haiku-latest", "claude-3-5-haiku"),
+        ] {
+            assert!(priced_as(&pricing, model, key), "{}", model);
+        }
+    }
+
+    #[test]
+    fn unknown_models() {
+        let pricing = Pricing::default();
+        assert!(pricing.price("").is_none());
+        assert!(pricing.price("mystery-model").is_none());
+        assert!(pricing.price("gpt-4o-audio-preview").is_none());
+        assert!(pricing.price("gpt-4o-2024-08").is_none());
+    }
+
+    #[test]
+    fn overrides_replace_built_in_prices() {
+        let price = ModelPrice {
+            input: 1.0,
+            output: 2.0,
+            cache_read: None,
+            cache_write_5m: None,
+            cache_write_1h: None,
+            batch_input: None,
+            batch_output: None,
+        };
+        let overrides = HashMap::from([(" GPT-4o ".to_string(), price)]);
+        let pricing = Pricing::new(&overrides);
+        assert_eq!(pricing.price("gpt-4o-2024-08-06").unwrap().input, 1.0);
+        assert!(pricing.price("gpt-4o-mini").unwrap().input != 1.0);
+    }
+
+    #[test]
+    fn snapshot_suffixes() {
+        for rest in ["-2024-08-06", "-20250514", "-0613", "-latest"] {
+            assert!(is_snapshot_suffix(rest), "{}", rest);
+        }
+        for rest in [
+            "",
+            "-",
+            "-mini",
+            "-pro",
+            "-2024-8-6",
+            "-12345",
+            "-2024-08-06-mini",
+            "2024-08-06",
+            "-latest-2",
+        ] {
+            assert!(!is_snapshot_suffix(rest), "{}", rest);
+        }
+    }
+}
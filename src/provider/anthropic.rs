@@ -0,0 +1,184 @@
+use crate::api::anthropic::{AnthropicClient, DEFAULT_BASE_URL};
+use crate::api::progress::Progress;
+use crate::models::{BucketWidth, DailyData, DailyUsageData, Report};
+use crate::provider::UsageProvider;
+use crate::ui::colors::ColorPalette;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+const CENTS_TO_DOLLARS: f64 = 100.0;
+
+pub const ID: &str = "anthropic";
+
+#[derive(Clone)]
+pub struct AnthropicProvider {
+    base_url: String,
+    client: Option<AnthropicClient>,
+}
+
+impl AnthropicProvider {
+    pub fn new(base_url: Option<String>) -> Self {
+        Self {
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            client: None,
+        }
+    }
+
+    fn client(&self) -> Result<&AnthropicClient> {
+        self.client
+            .as_ref()
+            .context("Anthropic admin key is not set")
+    }
+}
+
+#[async_trait]
+impl UsageProvider for AnthropicProvider {
+    fn id(&self) -> &'static str {
+        ID
+    }
+
+    fn label(&self) -> &'static str {
+        "Anthropic"
+    }
+
+    fn env_var(&self) -> &'static str {
+        "ANTHROPIC_ADMIN_KEY"
+    }
+
+    fn admin_key_url(&self) -> &'static str {
+        "https://console.anthropic.com/settings/admin-keys"
+    }
+
+    fn palette(&self) -> ColorPalette {
+        ColorPalette::anthropic()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn connect(&self, api_key: String) -> Arc<dyn UsageProvider> {
+        Arc::new(Self {
+            base_url: self.base_url.clone(),
+            client: Some(AnthropicClient::new(api_key, &self.base_url)),
+        })
+    }
+
+    async fn fetch_costs(&self, start_time: DateTime<Utc>) -> Result<Report<DailyData>> {
+        let buckets = self.client()?.fetch_costs(start_time).await?;
+        let truncated = buckets
+            .truncated
+            .map(|t| format!("Cost report incomplete: {}", t));
+
+        let mut bucket_dates = Vec::new();
+        let mut cost_data = Vec::new();
+        for bucket in buckets.items {
+            if let Ok(bucket_start) = DateTime::parse_from_rfc3339(&bucket.starting_at) {
+                let date = bucket_start.with_timezone(&Utc);
+                bucket_dates.push(date);
+                for result in bucket.results {
+                    if let Ok(cost_cents) = result.amount.parse::<f64>() {
+                        let cost = cost_cents / CENTS_TO_DOLLARS;
+                        if cost > 0.0 {
+                            cost_data.push(DailyData {
+                                date,
+                                cost,
+                                line_item: result.model,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(Report::new(bucket_dates, cost_data).with_truncation(truncated))
+    }
+
+    async fn fetch_usage(
+        &self,
+        start_time: DateTime<Utc>,
+        width: BucketWidth,
+    ) -> Result<Report<DailyUsageData>> {
+        let buckets = self.client()?.fetch_usage(start_time, width).await?;
+        let truncated = buckets
+            .truncated
+            .map(|t| format!("Usage report incomplete: {}", t));
+
+        let mut bucket_dates = Vec::new();
+        let mut usage_data = Vec::new();
+        for bucket in buckets.items {
+            if let Ok(bucket_start) = DateTime::parse_from_rfc3339(&bucket.starting_at) {
+                let date = bucket_start.with_timezone(&Utc);
+                bucket_dates.push(date);
+                for result in bucket.results {
+                    let input_tokens = result.uncached_input_tokens
+                        + result.cache_creation.ephemeral_1h_input_tokens
+                        + result.cache_creation.ephemeral_5m_input_tokens
+                        + result.cache_read_input_tokens;
+
+                    if input_tokens > 0 || result.output_tokens > 0 {
+                        usage_data.push(DailyUsageData {
+                            date,
+                            input_tokens,
+                            output_tokens: result.output_tokens,
+                            api_key_id: result.api_key_id,
+                            model: result.model,
+                            cache_read_input_tokens: Some(result.cache_read_input_tokens),
+                            uncached_input_tokens: Some(result.uncached_input_tokens),
+                            cache_write_5m_tokens: Some(
+                                result.cache_creation.ephemeral_5m_input_tokens,
+                            ),
+                            cache_write_1h_tokens: Some(
+                                result.cache_creation.ephemeral_1h_input_tokens,
+                            ),
+                            num_requests: None,
+                            batch: result.service_tier.as_deref() == Some("batch"),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(Report::new(bucket_dates, usage_data).with_truncation(truncated))
+    }
+
+    async fn resolve_api_key_names(
+        &self,
+        api_key_ids: &[String],
+    ) -> Result<HashMap<String, String>> {
+        let client = self.client()?;
+        // Dropping the set aborts the lookups, so they stop with the fetch.
+        let mut lookups = JoinSet::new();
+        for api_key_id in api_key_ids.iter().cloned() {
+            let client = client.clone();
+            lookups.spawn(async move {
+                let result = client.fetch_api_key_name(&api_key_id).await;
+                (api_key_id, result)
+            });
+        }
+
+        // Keys that can't be resolved (e.g. deleted) fall back to their abbreviated id.
+        let mut api_key_names = HashMap::new();
+        while let Some(result) = lookups.join_next().await {
+            if let Ok((api_key_id, Ok(name))) = result {
+                api_key_names.insert(api_key_id, name);
+            }
+        }
+        Ok(api_key_names)
+    }
+
+    fn take_retries(&self) -> u32 {
+        self.client
+            .as_ref()
+            .map_or(0, AnthropicClient::take_retries)
+    }
+
+    fn progress(&self) -> Progress {
+        self.client
+            .as_ref()
+            .map(|client| client.progress().clone())
+            .unwrap_or_default()
+    }
+}
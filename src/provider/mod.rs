@@ -0,0 +1,290 @@
+pub mod anthropic;
+pub mod openai;
+
+pub use anthropic::AnthropicProvider;
+pub use openai::OpenAIProvider;
+
+use crate::api::progress::Progress;
+use crate::config::Config;
+use crate::models::{BucketWidth, DailyData, DailyUsageData, Report};
+use crate::ui::colors::ColorPalette;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::task::AbortHandle;
+
+/// A backend that can report cost and token usage for an organization.
+///
+/// Implementations normalize their vendor-specific responses into
+/// `DailyData`/`DailyUsageData` so the rest of the app never has to know
+/// which API the data came from.
+#[async_trait]
+pub trait UsageProvider: Send + Sync {
+    /// Stable identifier used in config files and on the command line.
+    fn id(&self) -> &'static str;
+
+    fn label(&self) -> &'static str;
+
+    /// Environment variable holding the admin key for this provider.
+    fn env_var(&self) -> &'static str;
+
+    /// Where users can create an admin key, shown in the API key popup.
+    fn admin_key_url(&self) -> &'static str;
+
+    fn palette(&self) -> ColorPalette;
+
+    fn is_connected(&self) -> bool;
+
+    /// Returns a copy of this provider that authenticates with `api_key`.
+    fn connect(&self, api_key: String) -> Arc<dyn UsageProvider>;
+
+    async fn fetch_costs(&self, start_time: DateTime<Utc>) -> Result<Report<DailyData>>;
+
+    /// Cost reports only come in daily buckets, usage can be broken down further.
+    async fn fetch_usage(
+        &self,
+        start_time: DateTime<Utc>,
+        width: BucketWidth,
+    ) -> Result<Report<DailyUsageData>>;
+
+    async fn resolve_api_key_names(
+        &self,
+        api_key_ids: &[String],
+    ) -> Result<HashMap<String, String>>;
+
+    /// Requests retried after a transient failure since the last call.
+    fn take_retries(&self) -> u32;
+
+    /// Endpoints of the fetch in progress (or the last one). Detached from
+    /// everything when there's no key.
+    fn progress(&self) -> Progress;
+}
+
+/// All providers toktop knows about, in the order they appear in the UI.
+pub fn registry(config: &Config) -> Vec<Arc<dyn UsageProvider>> {
+    vec![
+        Arc::new(OpenAIProvider::new(config.base_url(openai::ID))),
+        Arc::new(AnthropicProvider::new(config.base_url(anthropic::ID))),
+    ]
+}
+
+/// One organization of a provider, authenticated with its own admin key.
+pub struct Account {
+    pub provider: Arc<dyn UsageProvider>,
+    /// `None` for the provider's default key.
+    pub name: Option<String>,
+    /// Environment variable the admin key is read from.
+    pub env_var: String,
+}
+
+/// Accounts to show, grouped by provider in registry order. A provider gets
+/// one account per name listed in the config file or found as a
+/// `<ENV_VAR>_<NAME>` variable, plus its default account if `<ENV_VAR>` is
+/// set or there are no named ones.
+pub fn accounts(config: &Config) -> Vec<Account> {
+    let env: Vec<String> = std::env::vars().map(|(key, _)| key).collect();
+    let mut accounts = Vec::new();
+    for provider in registry(config) {
+        let default_var = provider.env_var();
+        let prefix = format!("{}_", default_var);
+
+        let mut named: Vec<(String, String)> = config
+            .accounts(provider.id())
+            .iter()
+            .map(|name| {
+                let var = format!("{}{}", prefix, name.to_uppercase());
+                (name.to_lowercase(), var)
+            })
+            .collect();
+        for var in &env {
+            if let Some(suffix) = var.strip_prefix(&prefix).filter(|s| !s.is_empty()) {
+                let name = suffix.to_lowercase();
+                if !named.iter().any(|(existing, _)| *existing == name) {
+                    named.push((name, var.clone()));
+                }
+            }
+        }
+        named.sort();
+
+        if named.is_empty() || env.iter().any(|var| var == default_var) {
+            accounts.push(Account {
+                provider: provider.clone(),
+                name: None,
+                env_var: default_var.to_string(),
+            });
+        }
+        for (name, env_var) in named {
+            accounts.push(Account {
+                provider: provider.clone(),
+                name: Some(name),
+                env_var,
+            });
+        }
+    }
+    accounts
+}
+
+/// Accounts matching `selector`, a provider id optionally followed by
+/// `/<account>` (all of them by default), connected with their keys from the
+/// environment. Used by the headless commands, which can't prompt for keys.
+pub fn connect_accounts(
+    accounts: Vec<Account>,
+    selector: Option<&str>,
+) -> Result<Vec<ProviderInfo>> {
+    let named = selector.is_some_and(|s| s.contains('/'));
+    let mut connected = Vec::new();
+    for account in accounts {
+        let mut info = ProviderInfo::new(account);
+        if selector.is_some_and(|s| !info.matches(s)) {
+            continue;
+        }
+        match std::env::var(&info.env_var) {
+            Ok(key) if !key.is_empty() => {
+                info.provider = info.provider.connect(key);
+                connected.push(info);
+            }
+            // Asked for by name, so a missing key is an error rather than
+            // an account to skip.
+            _ if named => bail!("{} is not set", info.env_var),
+            _ => {}
+        }
+    }
+
+    if connected.is_empty() {
+        match selector {
+            Some(selector) => bail!("No account with a key matches '{}'", selector),
+            None => bail!("No provider has a key set"),
+        }
+    }
+    Ok(connected)
+}
+
+/// Index of an account in `App::providers`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ProviderId(pub usize);
+
+#[derive(Default, Clone)]
+pub struct ProviderErrors {
+    pub cost: Option<String>,
+    pub usage: Option<String>,
+}
+
+/// A fetch running in the background for one account.
+pub struct InFlightFetch {
+    /// Tells this fetch's outcome apart from one of a fetch it superseded.
+    pub generation: u64,
+    /// Set once the task is spawned.
+    pub handle: Option<AbortHandle>,
+    /// Errors shown before the fetch started, put back if it's cancelled.
+    pub previous_errors: ProviderErrors,
+}
+
+pub struct ProviderInfo {
+    pub provider: Arc<dyn UsageProvider>,
+    /// `None` for the provider's default account.
+    pub account: Option<String>,
+    pub env_var: String,
+    /// `cache::key_hash` of the key the provider is connected with.
+    pub key_hash: Option<u64>,
+    pub errors: ProviderErrors,
+    /// The fetch in flight for this account, if any.
+    pub fetch: Option<InFlightFetch>,
+    pub initial_fetch_done: bool,
+    pub cost_data: Vec<DailyData>,
+    pub usage_data: Vec<DailyUsageData>,
+    /// Days the API returned a bucket for, used to tell "no data" apart
+    /// from "no spend".
+    pub cost_buckets: Vec<DateTime<Utc>>,
+    pub usage_buckets: Vec<DateTime<Utc>>,
+    /// Hourly usage for the 24h/72h ranges, refetched in full each time.
+    pub hourly_usage_data: Vec<DailyUsageData>,
+    pub hourly_usage_buckets: Vec<DateTime<Utc>>,
+    pub hourly_updated: Option<DateTime<Utc>>,
+    /// Minute buckets for the live view.
+    pub live_usage_data: Vec<DailyUsageData>,
+    pub live_updated: Option<DateTime<Utc>>,
+    pub api_key_names: HashMap<String, String>,
+    pub last_updated: Option<DateTime<Utc>>,
+    /// Requests retried during the last range fetch.
+    pub retries: u32,
+    /// Ways the last range fetch came back incomplete without failing, like
+    /// a report whose pages couldn't all be followed.
+    pub warnings: Vec<String>,
+    /// Earliest day the fetched data is complete from.
+    pub covered_from: Option<DateTime<Utc>>,
+    pub cost_chart_scroll: usize,
+    pub usage_chart_scroll: usize,
+}
+
+impl ProviderInfo {
+    pub fn new(account: Account) -> Self {
+        Self {
+            provider: account.provider,
+            account: account.name,
+            env_var: account.env_var,
+            key_hash: None,
+            errors: ProviderErrors::default(),
+            fetch: None,
+            initial_fetch_done: false,
+            cost_data: Vec::new(),
+            usage_data: Vec::new(),
+            cost_buckets: Vec::new(),
+            usage_buckets: Vec::new(),
+            hourly_usage_data: Vec::new(),
+            hourly_usage_buckets: Vec::new(),
+            hourly_updated: None,
+            live_usage_data: Vec::new(),
+            live_updated: None,
+            api_key_names: HashMap::new(),
+            last_updated: None,
+            retries: 0,
+            warnings: Vec::new(),
+            covered_from: None,
+            cost_chart_scroll: usize::MAX,
+            usage_chart_scroll: usize::MAX,
+        }
+    }
+
+    /// Provider label, followed by the account name for named accounts.
+    pub fn label(&self) -> String {
+        match &self.account {
+            Some(name) => format!("{}/{}", self.provider.label(), name),
+            None => self.provider.label().to_string(),
+        }
+    }
+
+    /// Whether `selector`, a provider id optionally followed by
+    /// `/<account>`, picks this account.
+    pub fn matches(&self, selector: &str) -> bool {
+        let selector = selector.to_lowercase();
+        match selector.split_once('/') {
+            Some((id, name)) => id == self.provider.id() && self.account.as_deref() == Some(name),
+            None => selector == self.provider.id(),
+        }
+    }
+
+    /// File name of this account's cache entry.
+    pub fn cache_key(&self) -> String {
+        match &self.account {
+            Some(name) => format!("{}-{}", self.provider.id(), name),
+            None => self.provider.id().to_string(),
+        }
+    }
+}
+
+pub struct FetchOutcome {
+    pub provider: ProviderId,
+    /// Start of the requested window; buckets before this were not refetched.
+    pub start_time: DateTime<Utc>,
+    pub width: BucketWidth,
+    pub fetched_at: DateTime<Utc>,
+    /// `None` when the request failed and existing data should be kept.
+    pub cost_data: Option<Report<DailyData>>,
+    pub usage_data: Option<Report<DailyUsageData>>,
+    pub api_key_names: HashMap<String, String>,
+    pub errors: ProviderErrors,
+    pub retries: u32,
+    pub warnings: Vec<String>,
+}
@@ -0,0 +1,151 @@
+use crate::api::openai::{OpenAIClient, DEFAULT_BASE_URL};
+use crate::api::progress::Progress;
+use crate::models::{BucketWidth, DailyData, DailyUsageData, Report};
+use crate::provider::UsageProvider;
+use crate::ui::colors::ColorPalette;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub const ID: &str = "openai";
+
+#[derive(Clone)]
+pub struct OpenAIProvider {
+    base_url: String,
+    client: Option<OpenAIClient>,
+}
+
+impl OpenAIProvider {
+    pub fn new(base_url: Option<String>) -> Self {
+        Self {
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            client: None,
+        }
+    }
+
+    fn client(&self) -> Result<&OpenAIClient> {
+        self.client.as_ref().context("OpenAI admin key is not set")
+    }
+}
+
+fn bucket_start(timestamp: i64, width: BucketWidth) -> DateTime<Utc> {
+    width.truncate(DateTime::from_timestamp(timestamp, 0).unwrap_or_default())
+}
+
+#[async_trait]
+impl UsageProvider for OpenAIProvider {
+    fn id(&self) -> &'static str {
+        ID
+    }
+
+    fn label(&self) -> &'static str {
+        "OpenAI"
+    }
+
+    fn env_var(&self) -> &'static str {
+        "OPENAI_ADMIN_KEY"
+    }
+
+    fn admin_key_url(&self) -> &'static str {
+        "https://platform.openai.com/settings/organization/admin-keys"
+    }
+
+    fn palette(&self) -> ColorPalette {
+        ColorPalette::openai()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn connect(&self, api_key: String) -> Arc<dyn UsageProvider> {
+        Arc::new(Self {
+            base_url: self.base_url.clone(),
+            client: Some(OpenAIClient::new(api_key, &self.base_url)),
+        })
+    }
+
+    async fn fetch_costs(&self, start_time: DateTime<Utc>) -> Result<Report<DailyData>> {
+        let buckets = self.client()?.fetch_costs(start_time).await?;
+        let truncated = buckets
+            .truncated
+            .map(|t| format!("Cost report incomplete: {}", t));
+
+        let mut bucket_dates = Vec::new();
+        let mut cost_data = Vec::new();
+        for bucket in buckets.items {
+            let date = bucket_start(bucket.start_time, BucketWidth::Day);
+            bucket_dates.push(date);
+
+            for result in bucket.results {
+                cost_data.push(DailyData {
+                    date,
+                    cost: result.amount.value(),
+                    line_item: result.line_item,
+                });
+            }
+        }
+        Ok(Report::new(bucket_dates, cost_data).with_truncation(truncated))
+    }
+
+    async fn fetch_usage(
+        &self,
+        start_time: DateTime<Utc>,
+        width: BucketWidth,
+    ) -> Result<Report<DailyUsageData>> {
+        let (buckets, truncations) = self.client()?.fetch_usage(start_time, width).await?;
+        let truncated = (!truncations.is_empty())
+            .then(|| format!("Usage report incomplete: {}", truncations.join("; ")));
+
+        let mut bucket_dates = Vec::new();
+        let mut usage_data = Vec::new();
+        for bucket in buckets {
+            let date = bucket_start(bucket.start_time, width);
+            bucket_dates.push(date);
+
+            for result in bucket.results {
+                if result.input_tokens > 0 || result.output_tokens > 0 {
+                    usage_data.push(DailyUsageData {
+                        date,
+                        input_tokens: result.input_tokens,
+                        output_tokens: result.output_tokens,
+                        api_key_id: result.api_key_id,
+                        model: result.model,
+                        cache_read_input_tokens: Some(result.input_cached_tokens),
+                        uncached_input_tokens: Some(
+                            result.input_tokens.saturating_sub(result.input_cached_tokens),
+                        ),
+                        cache_write_5m_tokens: None,
+                        cache_write_1h_tokens: None,
+                        num_requests: Some(result.num_model_requests),
+                        batch: result.batch.unwrap_or(false),
+                    });
+                }
+            }
+        }
+        Ok(Report::new(bucket_dates, usage_data).with_truncation(truncated))
+    }
+
+    async fn resolve_api_key_names(
+        &self,
+        api_key_ids: &[String],
+    ) -> Result<HashMap<String, String>> {
+        self.client()?
+            .fetch_api_key_names_for_ids(api_key_ids)
+            .await
+            .map_err(|e| anyhow::anyhow!("API key name fetch failed: {}", e))
+    }
+
+    fn take_retries(&self) -> u32 {
+        self.client.as_ref().map_or(0, OpenAIClient::take_retries)
+    }
+
+    fn progress(&self) -> Progress {
+        self.client
+            .as_ref()
+            .map(|client| client.progress().clone())
+            .unwrap_or_default()
+    }
+}
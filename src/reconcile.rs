@@ -0,0 +1,257 @@
+use crate::app::BucketWindow;
+use crate::models::{DailyData, DailyUsageData};
+use crate::pricing::Pricing;
+use crate::ui::content::shared;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Differences up to this many dollars are rounding.
+const TOLERANCE_DOLLARS: f64 = 0.01;
+/// Differences up to this share of the billed amount are close enough, since
+/// list prices round and change.
+const TOLERANCE_SHARE: f64 = 0.05;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    /// Billed about what the tokens cost.
+    Match,
+    /// Billed more than the tokens cost.
+    Over,
+    /// Billed less than the tokens cost.
+    Under,
+    /// Tokens of a model the pricing table doesn't know.
+    Unpriced,
+    /// Billed without any tokens under that name, e.g. tool fees or a model
+    /// the usage report calls something else.
+    NoUsage,
+    /// Tokens that nothing was billed for.
+    NotBilled,
+}
+
+impl Verdict {
+    pub fn label(self) -> &'static str {
+        match self {
+            Verdict::Match => "ok",
+            Verdict::Over => "billed more",
+            Verdict::Under => "billed less",
+            Verdict::Unpriced => "no price",
+            Verdict::NoUsage => "no usage",
+            Verdict::NotBilled => "not billed",
+        }
+    }
+}
+
+/// One model on one day of one account: what the cost report billed next to
+/// what its tokens cost at the pricing table's rates.
+#[derive(Serialize)]
+pub struct Reconciliation {
+    pub date: DateTime<Utc>,
+    pub provider: String,
+    /// Model name, or the cost line item when it doesn't name one.
+    pub model: String,
+    pub billed: f64,
+    /// `None` when some of the tokens have no price.
+    pub estimated: Option<f64>,
+    /// Billed minus estimated.
+    pub difference: Option<f64>,
+    pub tokens: u64,
+    pub verdict: Verdict,
+}
+
+/// Pairs the billed cost of each day and model in `window` with the cost of
+/// its tokens. OpenAI line items read "<model>, input", so only the part
+/// before the comma names the model.
+pub fn reconcile(
+    provider: &str,
+    cost_data: &[DailyData],
+    usage_data: &[DailyUsageData],
+    pricing: &Pricing,
+    window: BucketWindow,
+) -> Vec<Reconciliation> {
+    #[derive(Default)]
+    struct Sums {
+        billed: f64,
+        estimated: Option<f64>,
+        tokens: u64,
+        unpriced: bool,
+    }
+
+    let mut sums: BTreeMap<(DateTime<Utc>, String), Sums> = BTreeMap::new();
+    for d in cost_data.iter().filter(|d| window.contains(d.date)) {
+        let model = shared::extract_trimmed_string(&d.line_item)
+            .map_or("unattributed", |item| item.split(',').next().unwrap_or(item))
+            .trim()
+            .to_lowercase();
+        sums.entry((d.date, model)).or_default().billed += d.cost;
+    }
+    for d in usage_data.iter().filter(|d| window.contains(d.date)) {
+        let model = shared::extract_trimmed_string(&d.model)
+            .unwrap_or("unknown")
+            .to_lowercase();
+        let entry = sums.entry((d.date, model)).or_default();
+        entry.tokens += d.input_tokens + d.output_tokens;
+        match pricing.estimate(d) {
+            Some(cost) => *entry.estimated.get_or_insert(0.0) += cost,
+            None => entry.unpriced = true,
+        }
+    }
+
+    sums.into_iter()
+        // Zero-cost line items without tokens have nothing to compare.
+        .filter(|(_, sums)| sums.billed != 0.0 || sums.tokens > 0)
+        .map(|((date, model), sums)| {
+            let estimated = if sums.tokens == 0 {
+                Some(0.0)
+            } else {
+                sums.estimated.filter(|_| !sums.unpriced)
+            };
+            let difference = estimated.map(|estimated| sums.billed - estimated);
+            let tolerance = TOLERANCE_DOLLARS.max(sums.billed * TOLERANCE_SHARE);
+            let verdict = match difference {
+                _ if sums.tokens == 0 => Verdict::NoUsage,
+                None => Verdict::Unpriced,
+                Some(difference) if difference.abs() <= tolerance => Verdict::Match,
+                Some(_) if sums.billed == 0.0 => Verdict::NotBilled,
+                Some(difference) if difference > 0.0 => Verdict::Over,
+                Some(_) => Verdict::Under,
+            };
+            Reconciliation {
+                date,
+                provider: provider.to_string(),
+                model,
+                billed: sums.billed,
+                estimated,
+                difference,
+                tokens: sums.tokens,
+                verdict,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::DateWindow;
+    use crate::pricing::ModelPrice;
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
+
+    fn day(day: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(2024, 3, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    /// A dollar per million input or output tokens.
+    fn pricing() -> Pricing {
+        let price = ModelPrice {
+            input: 1.0,
+            output: 1.0,
+            cache_read: None,
+            cache_write_5m: None,
+            cache_write_1h: None,
+            batch_input: None,
+            batch_output: None,
+        };
+        Pricing::new(&HashMap::from([("test-model".to_string(), price)]))
+    }
+
+    fn cost(date: u32, cost: f64, line_item: &str) -> DailyData {
+        DailyData {
+            date: day(date),
+            cost,
+            line_item: Some(line_item.to_string()),
+        }
+    }
+
+    /// `millions` million tokens in and out, so $2 each at `pricing()`.
+    fn usage(date: u32, millions: u64, model: &str) -> DailyUsageData {
+        DailyUsageData {
+            date: day(date),
+            input_tokens: millions * 1_000_000,
+            output_tokens: millions * 1_000_000,
+            api_key_id: None,
+            model: Some(model.to_string()),
+            cache_read_input_tokens: None,
+            uncached_input_tokens: None,
+            cache_write_5m_tokens: None,
+            cache_write_1h_tokens: None,
+            num_requests: None,
+            batch: false,
+        }
+    }
+
+    fn verdicts(cost_data: &[DailyData], usage_data: &[DailyUsageData]) -> Vec<(String, Verdict)> {
+        let window = BucketWindow::from_dates(DateWindow {
+            start: day(1).date_naive(),
+            end: day(7).date_naive(),
+        });
+        reconcile("openai", cost_data, usage_data, &pricing(), window)
+            .into_iter()
+            .map(|row| (row.model, row.verdict))
+            .collect()
+    }
+
+    fn verdict(cost_data: &[DailyData], usage_data: &[DailyUsageData]) -> Verdict {
+        match verdicts(cost_data, usage_data).as_slice() {
+            [(_, verdict)] => *verdict,
+            rows => panic!("expected one row, got {}", rows.len()),
+        }
+    }
+
+    #[test]
+    fn billed_about_the_token_cost() {
+        let usage = [usage(2, 1, "test-model")];
+        assert!(verdict(&[cost(2, 2.0, "test-model")], &usage) == Verdict::Match);
+        // Within the 5% tolerance.
+        assert!(verdict(&[cost(2, 2.09, "Test-Model, input")], &usage) == Verdict::Match);
+    }
+
+    #[test]
+    fn billed_more() {
+        let usage = [usage(2, 1, "test-model")];
+        assert!(verdict(&[cost(2, 3.0, "test-model")], &usage) == Verdict::Over);
+    }
+
+    #[test]
+    fn billed_less() {
+        let usage = [usage(2, 1, "test-model")];
+        assert!(verdict(&[cost(2, 1.0, "test-model")], &usage) == Verdict::Under);
+    }
+
+    #[test]
+    fn tokens_without_price() {
+        let usage = [usage(2, 1, "mystery-model")];
+        assert!(verdict(&[cost(2, 2.0, "mystery-model")], &usage) == Verdict::Unpriced);
+    }
+
+    #[test]
+    fn billed_without_tokens() {
+        assert!(verdict(&[cost(2, 0.5, "web search")], &[]) == Verdict::NoUsage);
+    }
+
+    #[test]
+    fn tokens_without_bill() {
+        assert!(verdict(&[], &[usage(2, 1, "test-model")]) == Verdict::NotBilled);
+    }
+
+    #[test]
+    fn zero_cost_rows_without_tokens_are_left_out() {
+        let cost_data = [cost(2, 0.0, "test-model"), cost(3, 2.0, "test-model")];
+        let usage_data = [usage(3, 1, "test-model"), usage(4, 0, "test-model")];
+        let rows = verdicts(&cost_data, &usage_data);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].1 == Verdict::Match);
+    }
+
+    #[test]
+    fn rows_outside_the_window_are_ignored() {
+        let cost_data = [cost(8, 2.0, "test-model")];
+        assert!(verdicts(&cost_data, &[usage(8, 1, "test-model")]).is_empty());
+    }
+}
@@ -0,0 +1,450 @@
+use crate::app::DateWindow;
+use crate::budget::{Budget, BudgetStatus, Period};
+use crate::config;
+use crate::digest::{self, Digest};
+use crate::fetch;
+use crate::models::BucketWidth;
+use crate::notify::{BudgetAlert, Channel, Notification, NotifyConfig};
+use crate::provider::{self, Account, FetchOutcome, ProviderId, ProviderInfo};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Timelike, Utc};
+use clap::Args;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::hash::Hash;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Address to serve /metrics on
+    #[arg(long, default_value = "127.0.0.1:9464")]
+    listen: SocketAddr,
+
+    /// How often to fetch, e.g. 90s, 5m or 1h
+    #[arg(long, default_value = "5m", value_parser = parse_interval)]
+    interval: Duration,
+
+    /// Provider to fetch, optionally narrowed to one account, e.g. openai or
+    /// openai/prod (defaults to every account with a key)
+    #[arg(long)]
+    provider: Option<String>,
+}
+
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    match config::parse_interval(s) {
+        Ok(interval) if !interval.is_zero() => Ok(interval),
+        Ok(_) => Err("the interval must be longer than zero".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// An account with what its last fetches found. Failed reports keep the
+/// data from before, so a flaky API doesn't make spend drop to zero.
+struct AccountState {
+    info: ProviderInfo,
+    cost_errors: u64,
+    usage_errors: u64,
+}
+
+/// What each notification channel (by index) has been sent, so each alert
+/// and digest reaches every channel once.
+#[derive(Default)]
+struct Sent {
+    /// Budgets by index, with the start of the period they were exceeded in.
+    budget_alerts: HashSet<(usize, (usize, DateTime<Utc>))>,
+    /// Days whose digest went out.
+    digests: HashSet<(usize, NaiveDate)>,
+}
+
+/// Sends the notification `key` stands for to the channels that haven't
+/// had it yet and records the ones it reached. A channel that fails is
+/// tried again next time without repeating it on the others.
+async fn send_pending<K: Copy + Eq + Hash>(
+    channels: &[Channel],
+    sent: &mut HashSet<(usize, K)>,
+    key: K,
+    notification: impl FnOnce() -> Notification,
+) {
+    let pending: Vec<usize> = (0..channels.len())
+        .filter(|i| !sent.contains(&(*i, key)))
+        .collect();
+    if pending.is_empty() {
+        return;
+    }
+    let notification = notification();
+    for i in pending {
+        match channels[i].send(&notification).await {
+            Ok(()) => {
+                sent.insert((i, key));
+            }
+            Err(e) => eprintln!("Warning: Failed to send notification: {:#}", e),
+        }
+    }
+}
+
+/// Fetches every `interval` and serves today's data in the Prometheus text
+/// format on `/metrics` until the process is killed. Exceeded budgets and
+/// the daily digest go to the notification channels.
+pub async fn run(
+    accounts: Vec<Account>,
+    budgets: Vec<Budget>,
+    notify: NotifyConfig,
+    args: ServeArgs,
+) -> Result<()> {
+    let accounts = provider::connect_accounts(accounts, args.provider.as_deref())?;
+    let listener = TcpListener::bind(args.listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", args.listen))?;
+    eprintln!("Serving metrics on http://{}/metrics", args.listen);
+
+    let states: Vec<AccountState> = accounts
+        .into_iter()
+        .map(|info| AccountState {
+            info,
+            cost_errors: 0,
+            usage_errors: 0,
+        })
+        .collect();
+    let metrics = Arc::new(Mutex::new(render(&states)));
+    tokio::spawn(fetch_loop(
+        states,
+        budgets,
+        notify,
+        args.interval,
+        metrics.clone(),
+    ));
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let _ = respond(stream, &metrics).await;
+        });
+    }
+}
+
+async fn fetch_loop(
+    mut states: Vec<AccountState>,
+    budgets: Vec<Budget>,
+    notify: NotifyConfig,
+    interval: Duration,
+    metrics: Arc<Mutex<String>>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    let mut sent = Sent::default();
+    loop {
+        ticker.tick().await;
+        // Enough history for monthly budgets and the digest's week-over-week
+        // change; later passes only refetch the last few days.
+        let today = Utc::now().date_naive();
+        let required = Period::Monthly.start(today).min(
+            DateWindow::ending(today - ChronoDuration::days(1), digest::HISTORY_DAYS).start_time(),
+        );
+        let tasks: Vec<_> = states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| {
+                let info = &state.info;
+                tokio::spawn(fetch::fetch_data(
+                    ProviderId(i),
+                    info.provider.clone(),
+                    fetch::fetch_start_time(info.last_updated, info.covered_from, required),
+                    BucketWidth::Day,
+                    info.api_key_names.keys().cloned().collect(),
+                ))
+            })
+            .collect();
+        let mut complete = true;
+        for task in tasks {
+            match task.await {
+                Ok(outcome) => complete &= apply(&mut states, outcome),
+                Err(_) => complete = false,
+            }
+        }
+        let rendered = render(&states);
+        *metrics.lock().unwrap_or_else(|e| e.into_inner()) = rendered;
+
+        if !notify.channels.is_empty() {
+            let infos: Vec<&ProviderInfo> = states.iter().map(|state| &state.info).collect();
+            send_budget_alerts(&budgets, &infos, &notify, &mut sent).await;
+            if complete {
+                send_digest(&infos, &notify, &mut sent).await;
+            }
+        }
+    }
+}
+
+async fn send_budget_alerts(
+    budgets: &[Budget],
+    infos: &[&ProviderInfo],
+    notify: &NotifyConfig,
+    sent: &mut Sent,
+) {
+    let today = Utc::now().date_naive();
+    for (i, budget) in budgets.iter().enumerate() {
+        if !infos.iter().any(|info| budget.covers(info)) {
+            continue;
+        }
+        let status = BudgetStatus {
+            budget,
+            spent: budget.spent(infos, today),
+        };
+        if !status.breached() {
+            continue;
+        }
+        send_pending(
+            &notify.channels,
+            &mut sent.budget_alerts,
+            (i, budget.period.start(today)),
+            || Notification::BudgetExceeded(BudgetAlert::new(&status, today)),
+        )
+        .await;
+    }
+}
+
+/// Sends the digest of yesterday once `digest_hour` has passed. Only called
+/// after a fetch where every account succeeded, so it isn't missing any.
+async fn send_digest(infos: &[&ProviderInfo], notify: &NotifyConfig, sent: &mut Sent) {
+    let Some(hour) = notify.digest_hour else {
+        return;
+    };
+    let now = Utc::now();
+    let yesterday = now.date_naive() - ChronoDuration::days(1);
+    if now.hour() < hour {
+        return;
+    }
+    send_pending(&notify.channels, &mut sent.digests, yesterday, || {
+        Notification::Digest(Digest::new(infos, yesterday))
+    })
+    .await;
+}
+
+/// Merges a fetch into its account like the TUI does. Returns whether the
+/// fetch was complete.
+fn apply(states: &mut [AccountState], outcome: FetchOutcome) -> bool {
+    let state = &mut states[outcome.provider.0];
+    let info = &mut state.info;
+    if let Some(cost) = outcome.cost_data {
+        fetch::merge_since(&mut info.cost_data, cost.data, outcome.start_time, |d| {
+            d.date
+        });
+    }
+    if let Some(usage) = outcome.usage_data {
+        fetch::merge_since(&mut info.usage_data, usage.data, outcome.start_time, |d| {
+            d.date
+        });
+    }
+    info.api_key_names.extend(outcome.api_key_names);
+    if outcome.errors.cost.is_some() {
+        state.cost_errors += 1;
+    }
+    if outcome.errors.usage.is_some() {
+        state.usage_errors += 1;
+    }
+    let complete = outcome.errors.cost.is_none()
+        && outcome.errors.usage.is_none()
+        && outcome.warnings.is_empty();
+    if complete {
+        info.last_updated = Some(outcome.fetched_at);
+        info.covered_from = Some(
+            info.covered_from
+                .map_or(outcome.start_time, |from| from.min(outcome.start_time)),
+        );
+    }
+    info.errors = outcome.errors;
+    info.warnings = outcome.warnings;
+    complete
+}
+
+/// Answers one request. Only the request line is looked at, which always
+/// fits in the first read.
+async fn respond(mut stream: TcpStream, metrics: &Mutex<String>) -> std::io::Result<()> {
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut request_line = request.split_whitespace();
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            metrics.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        ),
+        _ => ("404 Not Found", "Not found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Samples of one metric, keyed by their labels so the output is stable.
+struct Metric {
+    name: &'static str,
+    help: &'static str,
+    kind: &'static str,
+    samples: BTreeMap<Vec<(&'static str, String)>, f64>,
+}
+
+impl Metric {
+    fn new(name: &'static str, kind: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind,
+            samples: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, labels: Vec<(&'static str, String)>, value: f64) {
+        *self.samples.entry(labels).or_insert(0.0) += value;
+    }
+
+    fn write(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} {}", self.name, self.kind);
+        for (labels, value) in &self.samples {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+                .collect();
+            let _ = writeln!(out, "{}{{{}}} {}", self.name, labels.join(","), value);
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn render(states: &[AccountState]) -> String {
+    let today = BucketWidth::Day.truncate(Utc::now());
+
+    let mut cost = Metric::new(
+        "toktop_daily_cost_dollars",
+        "gauge",
+        "Cost so far today (UTC) by line item.",
+    );
+    let mut input = Metric::new(
+        "toktop_daily_input_tokens",
+        "gauge",
+        "Input tokens so far today (UTC), including cached ones.",
+    );
+    let mut output = Metric::new(
+        "toktop_daily_output_tokens",
+        "gauge",
+        "Output tokens so far today (UTC).",
+    );
+    let mut cache_read = Metric::new(
+        "toktop_daily_cache_read_tokens",
+        "gauge",
+        "Input tokens read from the prompt cache so far today (UTC).",
+    );
+    let mut requests = Metric::new(
+        "toktop_daily_requests",
+        "gauge",
+        "Model requests so far today (UTC). OpenAI only.",
+    );
+    let mut up = Metric::new(
+        "toktop_fetch_up",
+        "gauge",
+        "Whether the last fetch of a report succeeded.",
+    );
+    let mut errors = Metric::new(
+        "toktop_fetch_errors_total",
+        "counter",
+        "Fetches of a report that failed since toktop started.",
+    );
+    let mut truncated = Metric::new(
+        "toktop_report_truncated",
+        "gauge",
+        "Whether the last fetch stopped before the last page, leaving totals low.",
+    );
+    let mut last_success = Metric::new(
+        "toktop_last_success_timestamp_seconds",
+        "gauge",
+        "When every report of an account was last fetched completely.",
+    );
+
+    for state in states {
+        let info = &state.info;
+        let provider = info.label();
+
+        for d in info.cost_data.iter().filter(|d| d.date == today) {
+            let model = d.line_item.clone().unwrap_or_default();
+            cost.add(
+                vec![("provider", provider.clone()), ("model", model)],
+                d.cost,
+            );
+        }
+        for d in info.usage_data.iter().filter(|d| d.date == today) {
+            let api_key = d
+                .api_key_id
+                .as_ref()
+                .map(|id| info.api_key_names.get(id).unwrap_or(id).clone())
+                .unwrap_or_default();
+            let labels = vec![
+                ("provider", provider.clone()),
+                ("model", d.model.clone().unwrap_or_default()),
+                ("api_key", api_key),
+            ];
+            input.add(labels.clone(), d.input_tokens as f64);
+            output.add(labels.clone(), d.output_tokens as f64);
+            if let Some(tokens) = d.cache_read_input_tokens {
+                cache_read.add(labels.clone(), tokens as f64);
+            }
+            if let Some(count) = d.num_requests {
+                requests.add(labels, count as f64);
+            }
+        }
+
+        // Until the first fetch finishes there's nothing to report on.
+        if info.last_updated.is_none() && state.cost_errors == 0 && state.usage_errors == 0 {
+            continue;
+        }
+        for (report, failed, count) in [
+            ("cost", info.errors.cost.is_some(), state.cost_errors),
+            ("usage", info.errors.usage.is_some(), state.usage_errors),
+        ] {
+            let labels = vec![
+                ("provider", provider.clone()),
+                ("report", report.to_string()),
+            ];
+            up.add(labels.clone(), if failed { 0.0 } else { 1.0 });
+            errors.add(labels, count as f64);
+        }
+        let is_truncated = !info.warnings.is_empty();
+        truncated.add(
+            vec![("provider", provider.clone())],
+            if is_truncated { 1.0 } else { 0.0 },
+        );
+        if let Some(updated) = info.last_updated {
+            last_success.add(vec![("provider", provider)], updated.timestamp() as f64);
+        }
+    }
+
+    let mut out = String::new();
+    for metric in [
+        cost,
+        input,
+        output,
+        cache_read,
+        requests,
+        up,
+        errors,
+        truncated,
+        last_success,
+    ] {
+        metric.write(&mut out);
+    }
+    out
+}
@@ -1,4 +1,3 @@
-use crate::provider::Provider;
 use ratatui::style::Color;
 
 pub struct ColorPalette {
@@ -11,14 +10,7 @@ pub struct ColorPalette {
 }
 
 impl ColorPalette {
-    pub fn for_provider(provider: Provider) -> Self {
-        match provider {
-            Provider::Anthropic => Self::anthropic(),
-            Provider::OpenAI => Self::openai(),
-        }
-    }
-
-    fn anthropic() -> Self {
+    pub fn anthropic() -> Self {
         Self {
             // Book Cloth - warm reddish-orange
             primary: Color::Rgb(0xCC, 0x78, 0x5C),
@@ -41,7 +33,7 @@ impl ColorPalette {
         }
     }
 
-    fn openai() -> Self {
+    pub fn openai() -> Self {
         Self {
             // Cool blue
             primary: Color::Cyan,
@@ -72,4 +64,17 @@ impl ColorPalette {
             selected_fg: Color::Black,
         }
     }
+
+    /// The "All providers" view, where series can come from any provider.
+    pub fn combined() -> Self {
+        Self {
+            primary: Color::Yellow,
+            accent: Color::Cyan,
+            error: Color::Red,
+            // Same spread as OpenAI's, which has the most distinct colors
+            chart_colors: Self::openai().chart_colors,
+            selected_bg: Color::Yellow,
+            selected_fg: Color::Black,
+        }
+    }
 }
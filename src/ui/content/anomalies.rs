@@ -0,0 +1,90 @@
+use crate::app::{App, GroupBy, Scope, View};
+use crate::ui::colors::ColorPalette;
+use crate::ui::content::shared;
+use crate::ui::utils::format_tokens;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+const MAX_ROWS: u16 = 6;
+
+/// Rows the panel needs for `scope`, borders included.
+pub fn panel_height(app: &App, scope: Scope) -> u16 {
+    (app.anomalies(scope).len() as u16).clamp(1, MAX_ROWS) + 2
+}
+
+pub fn render_anomalies_panel(
+    f: &mut Frame,
+    app: &App,
+    area: Rect,
+    scope: Scope,
+    palette: &ColorPalette,
+) {
+    let anomalies = app.anomalies(scope);
+    let window = app.range_window();
+    let format_value = |value: f64| match app.current_view {
+        View::Cost | View::Reconcile => format!("${:.2}", value),
+        View::Usage | View::Live => format!("{} tokens", format_tokens(value as u64)),
+    };
+    let series_name = |series: &str| match (app.group_by, scope) {
+        (GroupBy::ApiKeys, Scope::Provider(provider)) => app
+            .provider_info(provider)
+            .api_key_names
+            .get(series)
+            .cloned()
+            .unwrap_or_else(|| shared::abbreviate_api_key(series)),
+        _ => series.to_string(),
+    };
+
+    let mut lines: Vec<Line> = anomalies
+        .iter()
+        .take(MAX_ROWS as usize)
+        .map(|anomaly| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<12}", window.label(anomaly.date)),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(
+                    format!("{:.1}x ", anomaly.ratio()),
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format_value(anomaly.value),
+                    Style::default()
+                        .fg(palette.primary)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" (usually {}) ", format_value(anomaly.median)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(series_name(&anomaly.series)),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No unusual spikes in the selected range",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let title = if anomalies.len() > MAX_ROWS as usize {
+        format!("Anomalies ({}, newest {} shown)", anomalies.len(), MAX_ROWS)
+    } else {
+        format!("Anomalies ({})", anomalies.len())
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(palette.primary).add_modifier(Modifier::DIM))
+        .title(Span::styled(
+            title,
+            Style::default().fg(palette.primary).add_modifier(Modifier::BOLD),
+        ));
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
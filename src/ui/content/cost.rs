@@ -1,8 +1,9 @@
-use crate::app::{App, View};
-use crate::models::DailyData;
-use crate::provider::Provider;
+use crate::app::{App, BucketWindow, GroupBy, Scope, View};
+use crate::forecast::Forecast;
+use crate::models::{BucketWidth, DailyData};
 use crate::ui::colors::ColorPalette;
 use crate::ui::content::shared;
+use crate::ui::utils::format_forecast;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -36,12 +37,12 @@ fn filter_items_by_cost_threshold(
     filtered_items
 }
 
-fn process_cost_data(data: &[DailyData]) -> CostChartData {
+fn process_cost_data(data: &[DailyData], window: BucketWindow) -> CostChartData {
     let mut daily_costs: HashMap<String, HashMap<String, f64>> = HashMap::new();
     let mut item_totals: HashMap<String, f64> = HashMap::new();
 
     for d in data {
-        let date_str = d.date.format("%m/%d").to_string();
+        let date_str = window.label(d.date);
         let line_item = shared::extract_trimmed_string(&d.line_item)
             .unwrap_or("unknown")
             .to_string();
@@ -55,8 +56,9 @@ fn process_cost_data(data: &[DailyData]) -> CostChartData {
         *item_totals.entry(line_item).or_insert(0.0) += d.cost;
     }
 
-    let mut dates: Vec<String> = daily_costs.keys().cloned().collect();
-    dates.sort();
+    // Every bucket of the window gets a slot, so days without spend show up
+    // as zeros instead of being skipped.
+    let dates: Vec<String> = window.buckets().map(|date| window.label(date)).collect();
 
     let mut items: Vec<String> = item_totals.keys().cloned().collect();
     items.sort();
@@ -69,19 +71,40 @@ fn process_cost_data(data: &[DailyData]) -> CostChartData {
     }
 }
 
+/// Lines the cost legend adds below the items.
+struct LegendExtras<'a> {
+    daily_limit: Option<f64>,
+    forecasts: &'a HashMap<String, Forecast>,
+    /// Whether the chart draws the rest of the month.
+    projected: bool,
+}
+
 fn render_cost_legend(
     f: &mut Frame,
     area: Rect,
-    items: &[String],
-    item_totals: &HashMap<String, f64>,
+    chart_data: &CostChartData,
     item_colors: &HashMap<String, Color>,
     palette: &ColorPalette,
+    group_by: GroupBy,
+    extras: LegendExtras,
 ) {
+    let CostChartData {
+        items, item_totals, ..
+    } = chart_data;
+    let LegendExtras {
+        daily_limit,
+        forecasts,
+        projected,
+    } = extras;
     let legend_items = filter_items_by_cost_threshold(items, item_totals);
+    let legend_title = match group_by {
+        GroupBy::Provider => "Providers (>$1)",
+        _ => "Models (>$1)",
+    };
 
     let mut legend_lines = vec![
         Line::from(Span::styled(
-            "Models (>$1)",
+            legend_title,
             Style::default()
                 .fg(palette.primary)
                 .add_modifier(Modifier::BOLD),
@@ -121,6 +144,29 @@ fn render_cost_legend(
                     .add_modifier(Modifier::BOLD),
             ),
         ]));
+        if let Some(forecast) = forecasts.get(item) {
+            legend_lines.push(Line::from(vec![
+                Span::raw("     "),
+                Span::styled("Month-end: ", Style::default().fg(Color::Gray)),
+                Span::styled(format_forecast(forecast), Style::default().fg(Color::Gray)),
+            ]));
+        }
+    }
+
+    if projected {
+        legend_lines.push(Line::from(vec![
+            Span::styled("┈┈┈", Style::default().fg(Color::Gray)),
+            Span::raw(" "),
+            Span::raw("Projected rest of month"),
+        ]));
+    }
+
+    if let Some(limit) = daily_limit {
+        legend_lines.push(Line::from(vec![
+            Span::styled("───", Style::default().fg(palette.error)),
+            Span::raw(" "),
+            Span::raw(format!("Daily budget ${:.2}", limit)),
+        ]));
     }
 
     f.render_widget(
@@ -135,12 +181,25 @@ fn render_cost_chart(
     data: &[DailyData],
     area: Rect,
     title: &str,
-    provider: Provider,
+    scope: Scope,
     item_colors: &HashMap<String, Color>,
-    scroll_offset: usize,
 ) -> Option<usize> {
-    let palette = ColorPalette::for_provider(provider);
-    let chart_data = process_cost_data(data);
+    let palette = app.scope_palette(scope);
+    let window = app.range_window();
+    let mut chart_data = process_cost_data(data, window);
+    let forecasts = app.item_cost_forecasts(scope, &chart_data.items);
+    let total_forecast = app.cost_forecast(scope, app.selected_filter.as_deref());
+
+    // The rest of the month gets slots after today, drawn as the forecast.
+    let mut projection: HashMap<String, f64> = HashMap::new();
+    for (date, cost) in total_forecast
+        .as_ref()
+        .map_or(&[][..], |forecast| &forecast.remaining)
+    {
+        let label = window.label(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+        chart_data.dates.push(label.clone());
+        projection.insert(label, *cost);
+    }
 
     if chart_data.dates.is_empty() {
         app.chart_scrollbar_visible = false;
@@ -165,6 +224,13 @@ fn render_cost_chart(
     let inner = block.inner(area);
     f.render_widget(block, area);
 
+    // Only the unfiltered bars add up to what a daily budget limits.
+    let daily_limit = if app.selected_filter.is_none() {
+        app.daily_cost_limit(scope)
+    } else {
+        None
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Min(0), Constraint::Length(shared::LEGEND_WIDTH)])
@@ -173,21 +239,40 @@ fn render_cost_chart(
     render_cost_legend(
         f,
         chunks[1],
-        &chart_data.items,
-        &chart_data.item_totals,
-        &item_colors,
+        &chart_data,
+        item_colors,
         &palette,
+        app.group_by,
+        LegendExtras {
+            daily_limit,
+            forecasts: &forecasts,
+            projected: !projection.is_empty(),
+        },
     );
 
     let filtered_items = filter_items_by_cost_threshold(&chart_data.items, &chart_data.item_totals);
     let chart_items = &filtered_items;
 
+    let missing_dates = shared::missing_dates(window, &app.cost_buckets(scope));
+    let anomalous_dates = shared::anomalous_dates(app, scope);
     let chart_area = chunks[0];
+    let scroll_offset = *app.chart_scroll_mut(scope, View::Cost);
+    let bars = shared::StackedBars {
+        dates: &chart_data.dates,
+        missing_dates: &missing_dates,
+        anomalous_dates: &anomalous_dates,
+        items: chart_items,
+        item_colors,
+        max_total,
+        threshold: daily_limit.map(|limit| (limit, palette.error)),
+        projection: &projection,
+        scroll_offset,
+        show_segment_values: app.show_segment_values,
+    };
     match shared::render_vertical_stacked_bars(
         f,
         chart_area,
-        &chart_data.dates,
-        chart_items,
+        &bars,
         |date, item| {
             chart_data
                 .daily_costs
@@ -211,10 +296,6 @@ fn render_cost_chart(
                 format!("${:.2}", value)
             }
         },
-        item_colors,
-        max_total,
-        scroll_offset,
-        app.show_segment_values,
     ) {
         Some(layout) => {
             shared::handle_chart_scrollbar(
@@ -244,20 +325,25 @@ pub fn render_cost_view(
     f: &mut Frame,
     app: &mut App,
     area: Rect,
-    provider: Provider,
+    scope: Scope,
     palette: &ColorPalette,
 ) {
-    let has_client = app.has_client(provider);
-    let error = app.error_for_provider(provider, View::Cost).cloned();
+    let label = app.scope_label(scope);
+    let has_client = app.scope_has_client(scope);
+    let error = app.scope_error(scope, View::Cost);
     let filter_suffix = if let Some(ref filter) = app.selected_filter {
         format!(" - {}", filter)
     } else {
         String::new()
     };
+    let group_by_label = match app.group_by {
+        GroupBy::Provider => "Provider",
+        GroupBy::ProviderModel => "Provider + Model",
+        GroupBy::Model | GroupBy::ApiKeys => "Model",
+    };
     let title = format!(
-        "{} - Daily Cost by Model{}",
-        provider.label(),
-        filter_suffix
+        "{} - Daily Cost by {}{}",
+        label, group_by_label, filter_suffix
     );
 
     if let Some(err) = error {
@@ -265,7 +351,7 @@ pub fn render_cost_view(
             f,
             area,
             &title,
-            &format!("Error loading {} Cost data: {}", provider.label(), err),
+            &format!("Error loading {} Cost data: {}", label, err),
             palette.error,
         );
         return;
@@ -276,37 +362,34 @@ pub fn render_cost_view(
         return;
     }
 
-    let range_filtered_data = {
-        let data = match app.data_for_provider(provider) {
-            Some(values) => values,
-            None => {
-                shared::render_empty_state(
-                    f,
-                    area,
-                    &title,
-                    &format!("{} Cost data is not wired up yet.", provider.label()),
-                );
-                return;
-            }
-        };
-        app.filter_cost_data_by_range(data)
-    };
+    if app.range.bucket_width() != BucketWidth::Day {
+        shared::render_empty_state(
+            f,
+            area,
+            &title,
+            &format!(
+                "{} only reports cost per day. Pick a daily range to see cost.",
+                label
+            ),
+        );
+        return;
+    }
+
+    let range_filtered_data = app.filter_cost_data_by_range(&app.cost_data(scope));
 
     if range_filtered_data.is_empty() {
-        let msg = if app.loading {
-            format!("Loading {} Cost data...", provider.label())
+        let msg = if app.scope_is_loading(scope) {
+            format!("Loading {} Cost data...", label)
         } else {
-            format!(
-                "No {} Cost data available for the selected window.",
-                provider.label()
-            )
+            format!("No {} Cost data available for the selected window.", label)
         };
         shared::render_empty_state(f, area, &title, &msg);
         return;
     }
 
-    let all_items_chart_data = process_cost_data(&range_filtered_data);
-    let all_item_colors = shared::create_color_mapping(&all_items_chart_data.items, palette);
+    let all_items_chart_data = process_cost_data(&range_filtered_data, app.range_window());
+    let mut all_item_colors = shared::create_color_mapping(&all_items_chart_data.items, palette);
+    shared::apply_provider_colors(app, &mut all_item_colors);
 
     let filtered_data =
         shared::apply_filter(&range_filtered_data, app.selected_filter.as_ref(), |d| {
@@ -318,33 +401,17 @@ pub fn render_cost_view(
             f,
             area,
             &title,
-            &format!(
-                "No {} Cost data available for the selected window.",
-                provider.label()
-            ),
+            &format!("No {} Cost data available for the selected window.", label),
         );
         return;
     }
 
-    let chart_data = process_cost_data(&filtered_data);
+    let chart_data = process_cost_data(&filtered_data, app.range_window());
     let item_colors = shared::filter_item_colors(&all_item_colors, &chart_data.items);
 
-    let scroll_offset = {
-        let info = app.provider_info(provider);
-        info.cost_chart_scroll
-    };
-
-    if let Some(actual_scroll) = render_cost_chart(
-        f,
-        app,
-        &filtered_data,
-        area,
-        &title,
-        provider,
-        &item_colors,
-        scroll_offset,
-    ) {
-        let info = app.provider_info_mut(provider);
-        info.cost_chart_scroll = actual_scroll;
+    if let Some(actual_scroll) =
+        render_cost_chart(f, app, &filtered_data, area, &title, scope, &item_colors)
+    {
+        *app.chart_scroll_mut(scope, View::Cost) = actual_scroll;
     }
 }
@@ -0,0 +1,366 @@
+use crate::app::{App, BucketWindow, View, BURN_RATE_MINUTES};
+use crate::models::DailyUsageData;
+use crate::provider::ProviderId;
+use crate::ui::colors::ColorPalette;
+use crate::ui::content::shared;
+use crate::ui::utils::{format_estimate, format_tokens};
+use chrono::{DateTime, Utc};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph},
+    Frame,
+};
+use std::collections::HashMap;
+
+struct LiveChartData {
+    tokens: HashMap<String, Vec<(f64, f64)>>,
+    requests: HashMap<String, Vec<(f64, f64)>>,
+    items: Vec<String>,
+    max_tokens: f64,
+    max_requests: f64,
+    has_requests: bool,
+}
+
+fn process_live_data(data: &[DailyUsageData], window: BucketWindow) -> LiveChartData {
+    let slots: HashMap<DateTime<Utc>, usize> = window
+        .buckets()
+        .enumerate()
+        .map(|(idx, bucket)| (bucket, idx))
+        .collect();
+    let len = window.len as usize;
+
+    // Every minute of the window gets a point, so idle minutes drop to zero
+    // instead of the line interpolating across them.
+    let mut tokens: HashMap<String, Vec<u64>> = HashMap::new();
+    let mut requests: HashMap<String, Vec<u64>> = HashMap::new();
+    let mut has_requests = false;
+    for d in data {
+        let Some(&idx) = slots.get(&window.width.truncate(d.date)) else {
+            continue;
+        };
+        let model = shared::extract_trimmed_string(&d.model)
+            .unwrap_or("unknown")
+            .to_string();
+
+        tokens.entry(model.clone()).or_insert_with(|| vec![0; len])[idx] +=
+            d.input_tokens + d.output_tokens;
+        if let Some(num_requests) = d.num_requests {
+            has_requests = true;
+            requests.entry(model).or_insert_with(|| vec![0; len])[idx] += num_requests;
+        }
+    }
+
+    let to_points = |series: HashMap<String, Vec<u64>>| -> HashMap<String, Vec<(f64, f64)>> {
+        series
+            .into_iter()
+            .map(|(item, values)| {
+                let points = values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, value)| (idx as f64, value as f64))
+                    .collect();
+                (item, points)
+            })
+            .collect()
+    };
+    let max_of = |series: &HashMap<String, Vec<u64>>| {
+        series.values().flatten().copied().max().unwrap_or(0) as f64
+    };
+
+    let mut items: Vec<String> = tokens.keys().cloned().collect();
+    items.sort();
+
+    LiveChartData {
+        max_tokens: max_of(&tokens),
+        max_requests: max_of(&requests),
+        tokens: to_points(tokens),
+        requests: to_points(requests),
+        items,
+        has_requests,
+    }
+}
+
+/// Sum of the last complete minute across all series.
+fn last_full_minute(series: &HashMap<String, Vec<(f64, f64)>>, window: BucketWindow) -> f64 {
+    let idx = (window.len as usize).saturating_sub(2);
+    series
+        .values()
+        .filter_map(|points| points.get(idx))
+        .map(|(_, value)| value)
+        .sum()
+}
+
+/// One measure of the live view, charted per model.
+struct Throughput<'a> {
+    title: &'a str,
+    series: &'a HashMap<String, Vec<(f64, f64)>>,
+    max_value: f64,
+    format_value: fn(f64) -> String,
+}
+
+fn render_throughput_chart(
+    f: &mut Frame,
+    area: Rect,
+    throughput: Throughput,
+    items: &[String],
+    item_colors: &HashMap<String, Color>,
+    window: BucketWindow,
+    palette: &ColorPalette,
+) {
+    let Throughput {
+        title,
+        series,
+        max_value,
+        format_value,
+    } = throughput;
+    let datasets: Vec<Dataset> = items
+        .iter()
+        .filter_map(|item| {
+            let points = series.get(item)?;
+            let color = item_colors.get(item).copied().unwrap_or(Color::White);
+            Some(
+                Dataset::default()
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(color))
+                    .data(points),
+            )
+        })
+        .collect();
+
+    let y_max = max_value.max(1.0);
+    let last_bucket = window.end() - window.width.duration();
+    let mid_bucket = window.start + window.width.duration() * (window.len / 2) as i32;
+    let x_labels = vec![
+        Span::raw(window.label(window.start)),
+        Span::raw(window.label(mid_bucket)),
+        Span::raw(window.label(last_bucket)),
+    ];
+    let y_labels = vec![
+        Span::raw("0"),
+        Span::raw(format_value(y_max / 2.0)),
+        Span::raw(format_value(y_max)),
+    ];
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default().title(Span::styled(
+                title,
+                Style::default()
+                    .fg(palette.primary)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        )
+        .legend_position(None)
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .bounds([0.0, (window.len - 1).max(1) as f64])
+                .labels(x_labels),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .bounds([0.0, y_max])
+                .labels(y_labels),
+        );
+    f.render_widget(chart, area);
+}
+
+fn render_live_legend(
+    f: &mut Frame,
+    area: Rect,
+    items: &[String],
+    item_colors: &HashMap<String, Color>,
+    palette: &ColorPalette,
+) {
+    let mut legend_lines = vec![
+        Line::from(Span::styled(
+            "Models",
+            Style::default()
+                .fg(palette.primary)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for item in items {
+        let color = item_colors.get(item).copied().unwrap_or(Color::White);
+        legend_lines.push(Line::from(vec![
+            Span::styled("   ", Style::default().bg(color)),
+            Span::raw(" "),
+            Span::raw(item.clone()),
+        ]));
+    }
+    f.render_widget(
+        Paragraph::new(legend_lines).alignment(Alignment::Left),
+        area,
+    );
+}
+
+pub fn render_live_view(
+    f: &mut Frame,
+    app: &mut App,
+    area: Rect,
+    provider: ProviderId,
+    palette: &ColorPalette,
+) {
+    // Live charts always show the whole window.
+    app.chart_scrollbar_visible = false;
+
+    let label = app.account_label(provider);
+    let filter_suffix = if let Some(ref filter) = app.selected_filter {
+        format!(" - {}", filter)
+    } else {
+        String::new()
+    };
+    let title = format!("{} - Live Throughput by Model{}", label, filter_suffix);
+
+    if let Some(err) = app.error_for_provider(provider, View::Live) {
+        shared::render_error_message(
+            f,
+            area,
+            &title,
+            &format!("Error loading {} live data: {}", label, err),
+            palette.error,
+        );
+        return;
+    }
+
+    if !app.has_client(provider) {
+        shared::render_empty_state(f, area, &title, "");
+        return;
+    }
+
+    let info = app.provider_info(provider);
+    let Some(updated) = info.live_updated else {
+        shared::render_empty_state(f, area, &title, &format!("Loading {} live data...", label));
+        return;
+    };
+
+    let window = app.live_window(provider);
+    let data = shared::apply_filter(&info.live_usage_data, app.selected_filter.as_ref(), |d| {
+        shared::extract_trimmed_string(&d.model)
+    });
+    let chart_data = process_live_data(&data, window);
+    let item_colors = shared::create_color_mapping(&chart_data.items, palette);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(palette.primary).add_modifier(Modifier::DIM))
+        .title(Span::styled(
+            title,
+            Style::default().fg(palette.primary).add_modifier(Modifier::BOLD),
+        ));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+
+    let burn_rate = app
+        .burn_rate_per_hour(provider)
+        .map(|(rate, partial)| format!("{}/h", format_estimate(rate, partial)))
+        .unwrap_or_else(|| "n/a".to_string());
+    let mut header = vec![
+        Span::styled(
+            format!("Burn rate (last {}m): ", BURN_RATE_MINUTES),
+            Style::default().fg(Color::Gray),
+        ),
+        Span::styled(
+            burn_rate,
+            Style::default()
+                .fg(palette.primary)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("  Last minute: ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!(
+                "{} tokens",
+                format_tokens(last_full_minute(&chart_data.tokens, window) as u64)
+            ),
+            Style::default()
+                .fg(palette.primary)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ];
+    if chart_data.has_requests {
+        header.push(Span::styled(
+            format!(
+                ", {:.0} requests",
+                last_full_minute(&chart_data.requests, window)
+            ),
+            Style::default()
+                .fg(palette.primary)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    header.push(Span::styled(
+        format!("  Updated {}", updated.format("%H:%M:%S")),
+        Style::default().fg(Color::DarkGray),
+    ));
+    f.render_widget(Paragraph::new(Line::from(header)), rows[0]);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(shared::LEGEND_WIDTH)])
+        .split(rows[1]);
+    render_live_legend(f, columns[1], &chart_data.items, &item_colors, palette);
+
+    if chart_data.items.is_empty() {
+        f.render_widget(
+            Paragraph::new(format!("No {} usage in the last hour.", label))
+                .alignment(Alignment::Center),
+            columns[0],
+        );
+        return;
+    }
+
+    let charts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(columns[0]);
+
+    render_throughput_chart(
+        f,
+        charts[0],
+        Throughput {
+            title: "Tokens per minute",
+            series: &chart_data.tokens,
+            max_value: chart_data.max_tokens,
+            format_value: |value| format_tokens(value as u64),
+        },
+        &chart_data.items,
+        &item_colors,
+        window,
+        palette,
+    );
+
+    if chart_data.has_requests {
+        render_throughput_chart(
+            f,
+            charts[1],
+            Throughput {
+                title: "Requests per minute",
+                series: &chart_data.requests,
+                max_value: chart_data.max_requests,
+                format_value: |value| format!("{:.0}", value),
+            },
+            &chart_data.items,
+            &item_colors,
+            window,
+            palette,
+        );
+    } else {
+        f.render_widget(
+            Paragraph::new(format!("{} doesn't report request counts.", label))
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(Color::DarkGray)),
+            charts[1],
+        );
+    }
+}
@@ -1,18 +1,49 @@
+mod anomalies;
 mod cost;
+mod live;
+mod reconcile;
 pub mod shared;
 mod usage;
 
-use crate::app::{App, View};
-use crate::ui::colors::ColorPalette;
-use ratatui::layout::Rect;
+use crate::app::{App, Scope, View};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::Frame;
 
 pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
-    let provider = app.current_provider();
-    let palette = ColorPalette::for_provider(provider);
+    let scope = app.scope();
+    let palette = app.scope_palette(scope);
 
-    match app.current_view {
-        View::Cost => cost::render_cost_view(f, app, area, provider, &palette),
-        View::Usage => usage::render_usage_view(f, app, area, provider, &palette),
+    // The anomaly panel sits under the chart it annotates.
+    let charted = matches!(app.current_view, View::Cost | View::Usage);
+    let area = if app.show_anomalies && charted {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(anomalies::panel_height(app, scope)),
+            ])
+            .split(area);
+        anomalies::render_anomalies_panel(f, app, chunks[1], scope, &palette);
+        chunks[0]
+    } else {
+        area
+    };
+
+    match (app.current_view, scope) {
+        (View::Cost, _) => cost::render_cost_view(f, app, area, scope, &palette),
+        (View::Usage, _) => usage::render_usage_view(f, app, area, scope, &palette),
+        (View::Reconcile, _) => reconcile::render_reconcile_view(f, app, area, scope, &palette),
+        (View::Live, Scope::Provider(provider)) => {
+            live::render_live_view(f, app, area, provider, &palette)
+        }
+        (View::Live, _) => {
+            app.chart_scrollbar_visible = false;
+            shared::render_empty_state(
+                f,
+                area,
+                &format!("{} - Live Throughput", app.scope_label(scope)),
+                "Live throughput is shown per account. Pick one in the Providers column.",
+            );
+        }
     }
 }
@@ -0,0 +1,233 @@
+use crate::app::{App, Scope, View};
+use crate::models::BucketWidth;
+use crate::reconcile::{Reconciliation, Verdict};
+use crate::ui::colors::ColorPalette;
+use crate::ui::content::shared;
+use crate::ui::utils::format_tokens;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+const MODEL_WIDTH: usize = 32;
+const ACCOUNT_WIDTH: usize = 18;
+const AMOUNT_WIDTH: usize = 11;
+
+fn format_dollars(amount: Option<f64>) -> String {
+    amount.map_or("-".to_string(), |amount| format!("${:.2}", amount))
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() > width {
+        let kept: String = text.chars().take(width - 1).collect();
+        format!("{}…", kept)
+    } else {
+        text.to_string()
+    }
+}
+
+/// How far off a row is, so the worst ones come first. Rows without an
+/// estimate count by what was billed.
+fn severity(row: &Reconciliation) -> f64 {
+    match row.verdict {
+        Verdict::Match => 0.0,
+        _ => row.difference.map_or(row.billed, f64::abs),
+    }
+}
+
+fn verdict_color(verdict: Verdict, palette: &ColorPalette) -> Color {
+    match verdict {
+        Verdict::Match => Color::Green,
+        Verdict::Over => palette.error,
+        Verdict::Under | Verdict::NotBilled => Color::Yellow,
+        Verdict::Unpriced | Verdict::NoUsage => Color::Gray,
+    }
+}
+
+pub fn render_reconcile_view(
+    f: &mut Frame,
+    app: &mut App,
+    area: Rect,
+    scope: Scope,
+    palette: &ColorPalette,
+) {
+    app.chart_scrollbar_visible = false;
+    let label = app.scope_label(scope);
+    let filter_suffix = if let Some(ref filter) = app.selected_filter {
+        format!(" - {}", filter)
+    } else {
+        String::new()
+    };
+    let title = format!("{} - Billed vs Estimated Cost{}", label, filter_suffix);
+
+    if let Some(err) = app.scope_error(scope, View::Reconcile) {
+        shared::render_error_message(
+            f,
+            area,
+            &title,
+            &format!("Error loading {} data: {}", label, err),
+            palette.error,
+        );
+        return;
+    }
+
+    if !app.scope_has_client(scope) {
+        shared::render_empty_state(f, area, &title, "");
+        return;
+    }
+
+    if app.range.bucket_width() != BucketWidth::Day {
+        shared::render_empty_state(
+            f,
+            area,
+            &title,
+            &format!(
+                "{} only reports cost per day. Pick a daily range to reconcile.",
+                label
+            ),
+        );
+        return;
+    }
+
+    let mut rows: Vec<Reconciliation> = app
+        .reconciliation(scope)
+        .into_iter()
+        .filter(|row| {
+            app.selected_filter
+                .as_ref()
+                .is_none_or(|filter| row.model == *filter)
+        })
+        .collect();
+    if rows.is_empty() {
+        let msg = if app.scope_is_loading(scope) {
+            format!("Loading {} data...", label)
+        } else {
+            format!("No {} data available for the selected window.", label)
+        };
+        shared::render_empty_state(f, area, &title, &msg);
+        return;
+    }
+    rows.sort_by(|a, b| {
+        severity(b)
+            .total_cmp(&severity(a))
+            .then_with(|| b.date.cmp(&a.date))
+    });
+
+    let billed: f64 = rows.iter().map(|row| row.billed).sum();
+    let estimated: f64 = rows.iter().filter_map(|row| row.estimated).sum();
+    let off = rows
+        .iter()
+        .filter(|row| row.verdict != Verdict::Match)
+        .count();
+    let show_account = !matches!(scope, Scope::Provider(_));
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Billed: ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                format!("${:.2}", billed),
+                Style::default()
+                    .fg(palette.primary)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("  Estimated: ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                format!("${:.2}", estimated),
+                Style::default()
+                    .fg(palette.primary)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("  Difference: ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                format!("${:+.2}", billed - estimated),
+                Style::default()
+                    .fg(palette.primary)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("  {} of {} day/model pairs off", off, rows.len()),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    let mut header = format!("{:<7}", "Date");
+    if show_account {
+        header.push_str(&format!("{:<width$}", "Account", width = ACCOUNT_WIDTH));
+    }
+    header.push_str(&format!(
+        "{:<model$}{:>amount$}{:>amount$}{:>amount$}{:>9}  Note",
+        "Model",
+        "Billed",
+        "Estimated",
+        "Diff",
+        "Tokens",
+        model = MODEL_WIDTH,
+        amount = AMOUNT_WIDTH,
+    ));
+    lines.push(Line::from(Span::styled(
+        header,
+        Style::default()
+            .fg(palette.primary)
+            .add_modifier(Modifier::BOLD),
+    )));
+
+    // Two borders, the totals, a blank line and the header; the last row
+    // says how many didn't fit.
+    let capacity = (area.height as usize).saturating_sub(5);
+    let shown = if rows.len() > capacity {
+        capacity.saturating_sub(1)
+    } else {
+        rows.len()
+    };
+    for row in &rows[..shown] {
+        let mut text = format!("{:<7}", row.date.format("%m/%d"));
+        if show_account {
+            text.push_str(&format!(
+                "{:<width$}",
+                truncate(&row.provider, ACCOUNT_WIDTH - 1),
+                width = ACCOUNT_WIDTH
+            ));
+        }
+        text.push_str(&format!(
+            "{:<model$}{:>amount$}{:>amount$}{:>amount$}{:>9}  ",
+            truncate(&row.model, MODEL_WIDTH - 1),
+            format_dollars(Some(row.billed)),
+            format_dollars(row.estimated),
+            row.difference
+                .map_or("-".to_string(), |difference| format!("{:+.2}", difference)),
+            format_tokens(row.tokens),
+            model = MODEL_WIDTH,
+            amount = AMOUNT_WIDTH,
+        ));
+        lines.push(Line::from(vec![
+            Span::raw(text),
+            Span::styled(
+                row.verdict.label(),
+                Style::default().fg(verdict_color(row.verdict, palette)),
+            ),
+        ]));
+    }
+    if shown < rows.len() {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{} more; `toktop export --metric reconcile` lists them all",
+                rows.len() - shown
+            ),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(palette.primary).add_modifier(Modifier::DIM))
+        .title(Span::styled(
+            title,
+            Style::default().fg(palette.primary).add_modifier(Modifier::BOLD),
+        ));
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
@@ -1,11 +1,14 @@
+use crate::app::{App, BucketWindow, GroupBy, Scope};
 use crate::ui::colors::ColorPalette;
+use chrono::{DateTime, Utc};
 use ratatui::{
     layout::{Alignment, Rect},
     style::{Color, Modifier, Style},
+    text::Line,
     widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub const LEGEND_WIDTH: u16 = 38;
 pub const COST_THRESHOLD: f64 = 1.0;
@@ -72,17 +75,41 @@ pub fn vertical_bar_layout(
     None
 }
 
+/// Chart labels of the buckets in `window` the API returned nothing for.
+pub fn missing_dates(window: BucketWindow, bucket_dates: &[DateTime<Utc>]) -> HashSet<String> {
+    let present: HashSet<_> = bucket_dates.iter().copied().collect();
+    window
+        .buckets()
+        .filter(|date| !present.contains(date))
+        .map(|date| window.label(date))
+        .collect()
+}
+
+/// Labels of the buckets with a spike in any series the chart shows.
+pub fn anomalous_dates(app: &App, scope: Scope) -> HashSet<String> {
+    let window = app.range_window();
+    app.anomalies(scope)
+        .iter()
+        .map(|anomaly| window.label(anomaly.date))
+        .collect()
+}
+
 pub fn compact_date_label(date: &str, width: u16) -> String {
     if width >= date.len() as u16 {
         return date.to_string();
     }
 
-    let day_part = date.split('/').nth(1).unwrap_or(date);
-    if width >= day_part.len() as u16 {
-        return day_part.to_string();
+    // Hourly labels ("10/16 14:00") shrink to the time of day, daily ones
+    // ("10/16") to the day of the month.
+    let short_part = match date.split_once(' ') {
+        Some((_, time)) => time,
+        None => date.split('/').nth(1).unwrap_or(date),
+    };
+    if width >= short_part.len() as u16 {
+        return short_part.to_string();
     }
 
-    day_part.chars().take(width as usize).collect::<String>()
+    short_part.chars().take(width as usize).collect::<String>()
 }
 
 pub fn render_error_message(f: &mut Frame, area: Rect, title: &str, message: &str, color: Color) {
@@ -120,6 +147,23 @@ pub fn create_color_mapping(items: &[String], palette: &ColorPalette) -> HashMap
         .collect()
 }
 
+/// Grouped by provider, the combined view draws each provider in its own color.
+pub fn apply_provider_colors(app: &App, colors: &mut HashMap<String, Color>) {
+    if app.scope() != Scope::All || app.group_by != GroupBy::Provider {
+        return;
+    }
+    // Named accounts of the same provider would all get the same color, so
+    // they keep the palette's.
+    for id in app.provider_ids() {
+        if app.provider_info(id).account.is_some() {
+            continue;
+        }
+        if let Some(color) = colors.get_mut(&app.account_label(id)) {
+            *color = app.provider(id).palette().primary;
+        }
+    }
+}
+
 /// Calculate a display max that handles outliers gracefully.
 /// Returns (display_max, actual_max) where display_max may be capped if there are outliers.
 pub fn calculate_smart_scale(totals: &[f64]) -> (f64, f64) {
@@ -254,24 +298,50 @@ pub fn render_horizontal_scrollbar(
     f.render_stateful_widget(scrollbar, area, &mut scrollbar_state);
 }
 
+/// What a stacked bar chart shows, apart from how its values are read and
+/// formatted.
+pub struct StackedBars<'a> {
+    pub dates: &'a [String],
+    /// Dates the API returned nothing for, drawn as a hollow `?` slot.
+    pub missing_dates: &'a HashSet<String>,
+    /// Dates with a spike, marked with a red `!`.
+    pub anomalous_dates: &'a HashSet<String>,
+    pub items: &'a [String],
+    pub item_colors: &'a HashMap<String, Color>,
+    pub max_total: f64,
+    /// A limit drawn as a line across the chart, like a daily budget.
+    pub threshold: Option<(f64, Color)>,
+    /// Projected totals of dates still to come, drawn dotted.
+    pub projection: &'a HashMap<String, f64>,
+    pub scroll_offset: usize,
+    pub show_segment_values: bool,
+}
+
 pub fn render_vertical_stacked_bars<F, G>(
     f: &mut Frame,
     chart_area: Rect,
-    dates: &[String],
-    items: &[String],
+    bars: &StackedBars,
     get_value: F,
     get_total: G,
     format_total: impl Fn(f64) -> String,
     format_segment_value: impl Fn(f64) -> String,
-    item_colors: &HashMap<String, Color>,
-    max_total: f64,
-    scroll_offset: usize,
-    show_segment_values: bool,
 ) -> Option<VerticalBarLayout>
 where
     F: Fn(&str, &str) -> Option<f64>,
     G: Fn(&str) -> f64,
 {
+    let &StackedBars {
+        dates,
+        missing_dates,
+        anomalous_dates,
+        items,
+        item_colors,
+        max_total,
+        threshold,
+        projection,
+        scroll_offset,
+        show_segment_values,
+    } = bars;
     if chart_area.width == 0 || chart_area.height <= 1 || max_total <= 0.0 {
         return None;
     }
@@ -279,7 +349,12 @@ where
     // Calculate smart scale to handle outliers
     let totals: Vec<f64> = dates.iter().map(|d| get_total(d)).collect();
     let (display_max, _actual_max) = calculate_smart_scale(&totals);
-    let scale_max = display_max.max(1.0);
+    // A threshold or projection stays on the scale even when every bar is
+    // below it.
+    let scale_max = display_max
+        .max(threshold.map_or(0.0, |(limit, _)| limit))
+        .max(projection.values().copied().fold(0.0, f64::max))
+        .max(1.0);
 
     let label_height: u16 = 1;
     let value_label_height: u16 = 1;
@@ -294,10 +369,18 @@ where
     }
     let bars_y = chart_area.y + value_label_height;
 
-    let layout = match vertical_bar_layout(dates.len(), chart_area.width, scroll_offset) {
-        Some(layout) => layout,
-        None => return None,
-    };
+    let layout = vertical_bar_layout(dates.len(), chart_area.width, scroll_offset)?;
+
+    // Drawn first so the bars cover it, leaving the line in the gaps and
+    // above the bars that stay under it.
+    if let Some((limit, color)) = threshold {
+        let height = ((limit / scale_max) * bar_area_height as f64).round() as u16;
+        let line_y = bars_y + bar_area_height - height.clamp(1, bar_area_height);
+        f.render_widget(
+            Paragraph::new("─".repeat(chart_area.width as usize)).style(Style::default().fg(color)),
+            Rect::new(chart_area.x, line_y, chart_area.width, 1),
+        );
+    }
 
     let end_index = layout.start_index + layout.visible_bars;
 
@@ -354,28 +437,53 @@ where
             }
         }
 
+        let is_missing = missing_dates.contains(date);
+        let projected = projection.get(date).copied();
         if used_height == 0 && bar_area_height > 0 {
-            let marker_y = bars_y + bar_area_height - 1;
-            render_stacked_bar_segment(
-                f,
-                Rect::new(bar_x, marker_y, layout.bar_width, 1),
-                "",
-                Color::DarkGray,
-                Color::Black,
-            );
+            let marker_area = Rect::new(bar_x, bars_y + bar_area_height - 1, layout.bar_width, 1);
+            if let Some(projected) = projected {
+                let height = ((projected.min(scale_max) / scale_max) * bar_area_height as f64)
+                    .round()
+                    .max(1.0) as u16;
+                let area = Rect::new(
+                    bar_x,
+                    bars_y + bar_area_height - height,
+                    layout.bar_width,
+                    height,
+                );
+                render_projected_bar(f, area);
+                f.render_widget(
+                    Paragraph::new(format!("~{}", format_total(projected)))
+                        .alignment(Alignment::Center)
+                        .style(Style::default().fg(Color::DarkGray)),
+                    Rect::new(bar_x, area.y.saturating_sub(1), layout.bar_width, 1),
+                );
+            } else if is_missing {
+                // No bucket at all: leave the slot hollow so it doesn't read as $0.
+                f.render_widget(
+                    Paragraph::new("?")
+                        .alignment(Alignment::Center)
+                        .style(Style::default().fg(Color::DarkGray)),
+                    marker_area,
+                );
+            } else {
+                render_stacked_bar_segment(f, marker_area, "", Color::DarkGray, Color::Black);
+            }
         }
 
         // Render the total value label above the bar
+        let is_anomalous = anomalous_dates.contains(date);
         if total > 0.0 {
             if let Some(segment_area) = top_segment_area {
                 let label_y = segment_area.y.saturating_sub(1);
-                // Show capped indicator for outliers
-                let label_text = if is_capped {
-                    format!("{}", format_total(total))
+                let label_text = if is_anomalous {
+                    format!("!{}", format_total(total))
                 } else {
                     format_total(total)
                 };
-                let label_style = if is_capped {
+                let label_style = if is_anomalous {
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                } else if is_capped {
                     Style::default()
                         .fg(Color::Yellow)
                         .add_modifier(Modifier::BOLD)
@@ -400,8 +508,17 @@ where
             label_height,
         );
         let label_text = compact_date_label(date, layout.bar_width);
+        let label_style = if is_anomalous {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else if is_missing || projected.is_some() {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
         f.render_widget(
-            Paragraph::new(label_text).alignment(Alignment::Center),
+            Paragraph::new(label_text)
+                .alignment(Alignment::Center)
+                .style(label_style),
             label_area,
         );
     }
@@ -409,6 +526,22 @@ where
     Some(layout)
 }
 
+/// Dotted outline of a bar that hasn't happened yet.
+fn render_projected_bar(f: &mut Frame, area: Rect) {
+    let width = area.width as usize;
+    let side = if width >= 2 {
+        format!("┊{}┊", " ".repeat(width - 2))
+    } else {
+        "┊".repeat(width)
+    };
+    let mut lines = vec![Line::from("┈".repeat(width))];
+    lines.extend((1..area.height).map(|_| Line::from(side.clone())));
+    f.render_widget(
+        Paragraph::new(lines).style(Style::default().fg(Color::Gray)),
+        area,
+    );
+}
+
 pub fn handle_chart_scrollbar(
     f: &mut Frame,
     app: &mut crate::app::App,
@@ -1,9 +1,8 @@
-use crate::app::{App, GroupBy, View};
+use crate::app::{App, BucketWindow, GroupBy, Scope, View};
 use crate::models::DailyUsageData;
-use crate::provider::Provider;
 use crate::ui::colors::ColorPalette;
 use crate::ui::content::shared;
-use crate::ui::utils::format_tokens;
+use crate::ui::utils::{format_estimate, format_tokens};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -20,17 +19,24 @@ struct UsageChartData {
     items: Vec<String>,
 }
 
-fn process_usage_data(data: &[DailyUsageData], group_by: GroupBy) -> UsageChartData {
+fn process_usage_data(
+    data: &[DailyUsageData],
+    group_by: GroupBy,
+    window: BucketWindow,
+) -> UsageChartData {
     let mut daily_tokens: HashMap<String, HashMap<String, (u64, u64)>> = HashMap::new();
     let mut item_totals: HashMap<String, (u64, u64)> = HashMap::new();
 
     for d in data {
-        let date_str = d.date.format("%m/%d").to_string();
+        let date_str = window.label(d.date);
 
+        // Combined rows already carry their provider in `model`.
         let item_key = match group_by {
-            GroupBy::Model => shared::extract_trimmed_string(&d.model)
-                .unwrap_or("unknown")
-                .to_string(),
+            GroupBy::Model | GroupBy::Provider | GroupBy::ProviderModel => {
+                shared::extract_trimmed_string(&d.model)
+                    .unwrap_or("unknown")
+                    .to_string()
+            }
             GroupBy::ApiKeys => shared::extract_trimmed_string(&d.api_key_id)
                 .unwrap_or("unknown")
                 .to_string(),
@@ -49,8 +55,9 @@ fn process_usage_data(data: &[DailyUsageData], group_by: GroupBy) -> UsageChartD
         total.1 += d.output_tokens;
     }
 
-    let mut dates: Vec<String> = daily_tokens.keys().cloned().collect();
-    dates.sort();
+    // Every bucket of the window gets a slot, so days without spend show up
+    // as zeros instead of being skipped.
+    let dates: Vec<String> = window.buckets().map(|date| window.label(date)).collect();
 
     let mut items: Vec<String> = item_totals.keys().cloned().collect();
     items.sort();
@@ -66,16 +73,23 @@ fn process_usage_data(data: &[DailyUsageData], group_by: GroupBy) -> UsageChartD
 fn render_usage_legend(
     f: &mut Frame,
     area: Rect,
-    items: &[String],
-    item_totals: &HashMap<String, (u64, u64)>,
+    app: &App,
+    scope: Scope,
+    chart_data: &UsageChartData,
     item_colors: &HashMap<String, Color>,
     palette: &ColorPalette,
-    group_by: GroupBy,
-    api_key_names: &HashMap<String, String>,
 ) {
+    let group_by = app.group_by;
+    let no_names = HashMap::new();
+    let api_key_names = match scope {
+        Scope::Provider(provider) => &app.provider_info(provider).api_key_names,
+        Scope::Accounts(_) | Scope::All => &no_names,
+    };
+    let estimate = app.estimated_costs(scope);
     let legend_title = match group_by {
-        GroupBy::Model => "Models",
+        GroupBy::Model | GroupBy::ProviderModel => "Models",
         GroupBy::ApiKeys => "API Keys",
+        GroupBy::Provider => "Providers",
     };
 
     let mut legend_lines = vec![
@@ -88,15 +102,16 @@ fn render_usage_legend(
         Line::from(""),
     ];
 
-    for item in items {
+    for item in &chart_data.items {
         let color = item_colors.get(item).copied().unwrap_or(Color::White);
-        let (input_total, output_total) = item_totals.get(item).copied().unwrap_or((0, 0));
+        let (input_total, output_total) =
+            chart_data.item_totals.get(item).copied().unwrap_or((0, 0));
         let display_item = match group_by {
             GroupBy::ApiKeys => {
                 let fallback = shared::abbreviate_api_key(item);
                 api_key_names.get(item).cloned().unwrap_or(fallback)
             }
-            GroupBy::Model => item.clone(),
+            GroupBy::Model | GroupBy::Provider | GroupBy::ProviderModel => item.clone(),
         };
         legend_lines.push(Line::from(vec![
             Span::styled(
@@ -127,6 +142,17 @@ fn render_usage_legend(
                     .add_modifier(Modifier::BOLD),
             ),
         ]));
+        let (cost, partial) = estimate.total(Some(item));
+        if cost > 0.0 || partial {
+            legend_lines.push(Line::from(vec![
+                Span::raw("     "),
+                Span::styled("Est. cost: ", Style::default().fg(Color::Gray)),
+                Span::styled(
+                    format_estimate(cost, partial),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]));
+        }
     }
 
     f.render_widget(
@@ -139,17 +165,16 @@ fn render_usage_chart(
     f: &mut Frame,
     app: &mut App,
     area: Rect,
-    provider: Provider,
+    scope: Scope,
     item_colors: &HashMap<String, Color>,
     chart_data: &UsageChartData,
     title: &str,
-    scroll_offset: usize,
 ) -> Option<usize> {
-    let palette = ColorPalette::for_provider(provider);
+    let palette = app.scope_palette(scope);
 
     if chart_data.dates.is_empty() {
         app.chart_scrollbar_visible = false;
-        shared::render_empty_state(f, area, &title, "No data available");
+        shared::render_empty_state(f, area, title, "No data available");
         return None;
     }
 
@@ -180,25 +205,28 @@ fn render_usage_chart(
         .constraints([Constraint::Min(0), Constraint::Length(shared::LEGEND_WIDTH)])
         .split(inner);
 
-    let api_key_names = &app.provider_info(provider).api_key_names;
-
-    render_usage_legend(
-        f,
-        chunks[1],
-        &chart_data.items,
-        &chart_data.item_totals,
-        &item_colors,
-        &palette,
-        app.group_by,
-        api_key_names,
-    );
+    render_usage_legend(f, chunks[1], app, scope, chart_data, item_colors, &palette);
 
+    let missing_dates = shared::missing_dates(app.range_window(), &app.usage_buckets(scope));
+    let anomalous_dates = shared::anomalous_dates(app, scope);
     let chart_area = chunks[0];
+    let no_projection = HashMap::new();
+    let bars = shared::StackedBars {
+        dates: &chart_data.dates,
+        missing_dates: &missing_dates,
+        anomalous_dates: &anomalous_dates,
+        items: &chart_data.items,
+        item_colors,
+        max_total: max_total as f64,
+        threshold: None,
+        projection: &no_projection,
+        scroll_offset: *app.chart_scroll_mut(scope, View::Usage),
+        show_segment_values: app.show_segment_values,
+    };
     match shared::render_vertical_stacked_bars(
         f,
         chart_area,
-        &chart_data.dates,
-        &chart_data.items,
+        &bars,
         |date, item| {
             chart_data
                 .daily_tokens
@@ -220,10 +248,6 @@ fn render_usage_chart(
         },
         |total| format_tokens(total as u64),
         |value| format_tokens(value as u64),
-        &item_colors,
-        max_total as f64,
-        scroll_offset,
-        app.show_segment_values,
     ) {
         Some(layout) => {
             shared::handle_chart_scrollbar(
@@ -253,33 +277,37 @@ pub fn render_usage_view(
     f: &mut Frame,
     app: &mut App,
     area: Rect,
-    provider: Provider,
+    scope: Scope,
     palette: &ColorPalette,
 ) {
-    let has_client = app.has_client(provider);
-    let error = app.error_for_provider(provider, View::Usage).cloned();
+    let label = app.scope_label(scope);
+    let has_client = app.scope_has_client(scope);
+    let error = app.scope_error(scope, View::Usage);
     let group_by_label = match app.group_by {
         GroupBy::Model => "Model",
         GroupBy::ApiKeys => "API Keys",
+        GroupBy::Provider => "Provider",
+        GroupBy::ProviderModel => "Provider + Model",
     };
     let filter_suffix = if let Some(ref filter) = app.selected_filter {
-        let display_name = match app.group_by {
-            GroupBy::Model => filter.clone(),
-            GroupBy::ApiKeys => {
+        let display_name = match (app.group_by, scope) {
+            (GroupBy::ApiKeys, Scope::Provider(provider)) => {
                 let api_key_names = &app.provider_info(provider).api_key_names;
                 api_key_names
                     .get(filter)
                     .cloned()
                     .unwrap_or_else(|| "Unknown Key".to_string())
             }
+            _ => filter.clone(),
         };
         format!(" - {}", display_name)
     } else {
         String::new()
     };
     let title = format!(
-        "{} - Daily Token Usage by {}{}",
-        provider.label(),
+        "{} - {} Token Usage by {}{}",
+        label,
+        app.range.bucket_width().label(),
         group_by_label,
         filter_suffix
     );
@@ -289,7 +317,7 @@ pub fn render_usage_view(
             f,
             area,
             &title,
-            &format!("Error loading {} Usage data: {}", provider.label(), err),
+            &format!("Error loading {} Usage data: {}", label, err),
             palette.error,
         );
         return;
@@ -300,44 +328,31 @@ pub fn render_usage_view(
         return;
     }
 
-    let range_filtered_data = {
-        let usage_data = match app.usage_data_for_provider(provider) {
-            Some(values) => values,
-            None => {
-                shared::render_empty_state(
-                    f,
-                    area,
-                    &title,
-                    &format!("{} Usage data is not wired up yet.", provider.label()),
-                );
-                return;
-            }
-        };
-        app.filter_usage_data_by_range(usage_data)
-    };
+    let range_filtered_data = app.filter_usage_data_by_range(&app.usage_data(scope));
 
     if range_filtered_data.is_empty() {
-        let msg = if app.loading {
-            format!("Loading {} Usage data...", provider.label())
+        let msg = if app.scope_is_loading(scope) {
+            format!("Loading {} Usage data...", label)
         } else {
-            format!(
-                "No {} Usage data available for the selected window.",
-                provider.label()
-            )
+            format!("No {} Usage data available for the selected window.", label)
         };
         shared::render_empty_state(f, area, &title, &msg);
         return;
     }
 
-    let all_items_chart_data = process_usage_data(&range_filtered_data, app.group_by);
-    let all_item_colors = shared::create_color_mapping(&all_items_chart_data.items, palette);
+    let all_items_chart_data =
+        process_usage_data(&range_filtered_data, app.group_by, app.range_window());
+    let mut all_item_colors = shared::create_color_mapping(&all_items_chart_data.items, palette);
+    shared::apply_provider_colors(app, &mut all_item_colors);
 
     let filtered_data = shared::apply_filter(
         &range_filtered_data,
         app.selected_filter.as_ref(),
         |d| match app.group_by {
-            GroupBy::Model => shared::extract_trimmed_string(&d.model),
             GroupBy::ApiKeys => shared::extract_trimmed_string(&d.api_key_id),
+            GroupBy::Model | GroupBy::Provider | GroupBy::ProviderModel => {
+                shared::extract_trimmed_string(&d.model)
+            }
         },
     );
 
@@ -346,33 +361,17 @@ pub fn render_usage_view(
             f,
             area,
             &title,
-            &format!(
-                "No {} Usage data available for the selected window.",
-                provider.label()
-            ),
+            &format!("No {} Usage data available for the selected window.", label),
         );
         return;
     }
 
-    let chart_data = process_usage_data(&filtered_data, app.group_by);
+    let chart_data = process_usage_data(&filtered_data, app.group_by, app.range_window());
     let item_colors = shared::filter_item_colors(&all_item_colors, &chart_data.items);
 
-    let scroll_offset = {
-        let info = app.provider_info(provider);
-        info.usage_chart_scroll
-    };
-
-    if let Some(actual_scroll) = render_usage_chart(
-        f,
-        app,
-        area,
-        provider,
-        &item_colors,
-        &chart_data,
-        &title,
-        scroll_offset,
-    ) {
-        let info = app.provider_info_mut(provider);
-        info.usage_chart_scroll = actual_scroll;
+    if let Some(actual_scroll) =
+        render_usage_chart(f, app, area, scope, &item_colors, &chart_data, &title)
+    {
+        *app.chart_scroll_mut(scope, View::Usage) = actual_scroll;
     }
 }
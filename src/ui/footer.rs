@@ -1,16 +1,19 @@
+use crate::api::progress::{StepStatus, Steps};
 use crate::app::App;
+use crate::provider::ProviderId;
 use crate::ui::colors::ColorPalette;
+use crate::ui::utils::{format_budget_amount, format_countdown};
+use chrono::{Local, Utc};
 use ratatui::{
     layout::Rect,
-    style::Style,
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
-    let provider = app.current_provider();
-    let palette = ColorPalette::for_provider(provider);
+    let palette = app.scope_palette(app.scope());
 
     let mut spans = vec![
         Span::raw("Commands: "),
@@ -28,17 +31,128 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
     spans.push(Span::styled("d", Style::default().fg(palette.accent)));
     spans.push(Span::raw("=toggle details "));
 
+    spans.push(Span::raw("| "));
+    spans.push(Span::styled("a", Style::default().fg(palette.accent)));
+    spans.push(Span::raw("=anomalies "));
+
     spans.push(Span::raw("| "));
     spans.push(Span::styled("r", Style::default().fg(palette.primary)));
     spans.push(Span::raw("=refresh "));
+    if app.any_loading() {
+        spans.push(Span::raw("| "));
+        spans.push(Span::styled("Esc", Style::default().fg(palette.primary)));
+        spans.push(Span::raw("=cancel refresh "));
+    }
     spans.push(Span::raw("| "));
     spans.push(Span::styled("q", Style::default().fg(palette.error)));
     spans.push(Span::raw("=quit"));
 
+    if let Some(last_refresh) = app.last_refresh {
+        let mut status = format!(
+            "  Last refresh {}",
+            last_refresh.with_timezone(&Local).format("%H:%M:%S")
+        );
+        if let Some(auto_refresh) = &app.auto_refresh {
+            if app.any_loading() {
+                status.push_str(", refreshing...");
+            } else if let Some(next_at) = auto_refresh.next_at {
+                let countdown = format_countdown(next_at - Utc::now());
+                if auto_refresh.failures > 0 {
+                    status.push_str(&format!(
+                        ", retrying in {} ({} failed)",
+                        countdown, auto_refresh.failures
+                    ));
+                } else {
+                    status.push_str(&format!(", next in {}", countdown));
+                }
+            }
+        }
+        spans.push(Span::styled(status, Style::default().fg(Color::DarkGray)));
+    }
+
+    let mut lines = vec![Line::from(spans)];
+    if let Some(banner) = budget_banner(app, &palette) {
+        lines.push(banner);
+    }
+    let progress = app.scope_progress(app.scope());
+    if !progress.is_empty() {
+        lines.push(progress_line(app, &progress, &palette));
+    }
+
     f.render_widget(
-        Paragraph::new(vec![Line::from(spans)])
+        Paragraph::new(lines)
             .block(Block::default().borders(Borders::ALL))
             .alignment(ratatui::layout::Alignment::Center),
         area,
     );
 }
+
+/// Every budget past its warning threshold with how much of it is used, in
+/// the error color once any of them is exceeded.
+fn budget_banner<'a>(app: &App, palette: &ColorPalette) -> Option<Line<'a>> {
+    let alerts = app.budget_alerts();
+    if alerts.is_empty() {
+        return None;
+    }
+    let color = if alerts.iter().any(|status| status.breached()) {
+        palette.error
+    } else {
+        Color::Yellow
+    };
+    let text = alerts
+        .iter()
+        .map(|status| {
+            format!(
+                "{} {:.0}% used ({})",
+                status.budget.label(),
+                status.percent(),
+                format_budget_amount(status)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" | ");
+    Some(Line::from(Span::styled(
+        format!("⚠ Budget: {}", text),
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    )))
+}
+
+/// Each endpoint of the running fetches with a mark for whether it is
+/// pending, done or failed. Aggregates name the account before its steps.
+fn progress_line<'a>(
+    app: &App,
+    progress: &[(ProviderId, Steps)],
+    palette: &ColorPalette,
+) -> Line<'a> {
+    let mut spans = vec![Span::styled(
+        "Fetching: ",
+        Style::default().fg(Color::DarkGray),
+    )];
+    for (i, (id, steps)) in progress.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" | "));
+        }
+        if app.aggregate.is_some() {
+            spans.push(Span::raw(format!("{}: ", app.account_label(*id))));
+        }
+        if steps.is_empty() {
+            spans.push(Span::styled(
+                "starting",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        for (j, (name, status)) in steps.iter().enumerate() {
+            if j > 0 {
+                spans.push(Span::raw(" "));
+            }
+            let (mark, color) = match status {
+                StepStatus::Pending => ("…", Color::DarkGray),
+                StepStatus::Done => ("✓", Color::Green),
+                StepStatus::Failed => ("✗", palette.error),
+            };
+            spans.push(Span::raw(format!("{} ", name)));
+            spans.push(Span::styled(mark, Style::default().fg(color)));
+        }
+    }
+    Line::from(spans)
+}
@@ -1,11 +1,11 @@
 mod banner;
-mod colors;
+pub mod colors;
 pub mod content;
 mod footer;
 mod options;
 mod popup;
 mod render;
-mod summary;
-mod utils;
+pub mod summary;
+pub mod utils;
 
 pub use render::render;
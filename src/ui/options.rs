@@ -1,5 +1,4 @@
-use crate::app::{App, GroupBy, OptionsColumn, Range, View};
-use crate::provider::Provider;
+use crate::app::{App, GroupBy, OptionsColumn, Range, Scope, View};
 use crate::ui::colors::ColorPalette;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -10,8 +9,7 @@ use ratatui::{
 };
 
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
-    let provider = app.current_provider();
-    let palette = ColorPalette::for_provider(provider);
+    let palette = app.scope_palette(app.scope());
 
     let block = Block::default()
         .borders(Borders::ALL)
@@ -45,17 +43,23 @@ fn render_providers_column(f: &mut Frame, app: &App, area: Rect, palette: &Color
         app,
         area,
         palette,
-        OptionsColumn::Provider,
-        "Providers",
-        &[Provider::OpenAI, Provider::Anthropic],
-        |_app, item| item.label().to_string(),
-        |app, item| app.selected_provider == *item,
-        |app, item| {
-            if !app.has_client(*item) {
-                Style::default().fg(Color::DarkGray)
-            } else {
-                Style::default().fg(Color::White)
-            }
+        SimpleColumn {
+            column: OptionsColumn::Provider,
+            title: "Providers",
+            items: &app.scopes(),
+            label: &|app, item| match item {
+                Scope::Provider(provider) => app.account_label(*provider),
+                Scope::Accounts(_) => app.scope_label(*item),
+                Scope::All => "All".to_string(),
+            },
+            is_selected: &|app, item| app.scope() == *item,
+            default_style: &|app, item| {
+                if !app.scope_has_client(*item) {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default().fg(Color::White)
+                }
+            },
         },
     );
 }
@@ -66,18 +70,22 @@ fn render_metrics_column(f: &mut Frame, app: &App, area: Rect, palette: &ColorPa
         app,
         area,
         palette,
-        OptionsColumn::Metric,
-        "Metrics",
-        &[View::Usage, View::Cost],
-        |_app, item| {
-            match item {
-                View::Cost => "Cost",
-                View::Usage => "Usage",
-            }
-            .to_string()
+        SimpleColumn {
+            column: OptionsColumn::Metric,
+            title: "Metrics",
+            items: &[View::Usage, View::Cost, View::Live, View::Reconcile],
+            label: &|_app, item| {
+                match item {
+                    View::Cost => "Cost",
+                    View::Usage => "Usage",
+                    View::Live => "Live",
+                    View::Reconcile => "Reconcile",
+                }
+                .to_string()
+            },
+            is_selected: &|app, item| app.current_view == *item,
+            default_style: &|_app, _item| Style::default().fg(Color::Gray),
         },
-        |app, item| app.current_view == *item,
-        |_app, _item| Style::default().fg(Color::Gray),
     );
 }
 
@@ -87,27 +95,52 @@ fn render_range_column(f: &mut Frame, app: &App, area: Rect, palette: &ColorPale
         app,
         area,
         palette,
-        OptionsColumn::Range,
-        "Range",
-        &[Range::SevenDays, Range::ThirtyDays],
-        |_app, item| item.label().to_string(),
-        |app, item| app.range == *item,
-        |_app, _item| Style::default().fg(Color::Gray),
+        SimpleColumn {
+            column: OptionsColumn::Range,
+            title: "Range",
+            items: &Range::ALL,
+            label: &|app, item| {
+                let editable = *item == Range::Custom
+                    && app.range == Range::Custom
+                    && app.options_column == OptionsColumn::Range;
+                if editable {
+                    format!("{} ▶", item.label())
+                } else {
+                    item.label().to_string()
+                }
+            },
+            is_selected: &|app, item| app.range == *item,
+            default_style: &|_app, _item| Style::default().fg(Color::Gray),
+        },
     );
 }
 
+/// A column of the options panel and how to show its entries.
+struct SimpleColumn<'a, T> {
+    column: OptionsColumn,
+    title: &'a str,
+    items: &'a [T],
+    label: &'a dyn Fn(&App, &T) -> String,
+    is_selected: &'a dyn Fn(&App, &T) -> bool,
+    /// Style of an entry that isn't selected.
+    default_style: &'a dyn Fn(&App, &T) -> Style,
+}
+
 fn render_simple_column<T: Copy>(
     f: &mut Frame,
     app: &App,
     area: Rect,
     palette: &ColorPalette,
-    column: OptionsColumn,
-    title: &str,
-    items: &[T],
-    get_label: impl Fn(&App, &T) -> String,
-    is_selected: impl Fn(&App, &T) -> bool,
-    get_default_style: impl Fn(&App, &T) -> Style,
+    column: SimpleColumn<T>,
 ) {
+    let SimpleColumn {
+        column,
+        title,
+        items,
+        label: get_label,
+        is_selected,
+        default_style: get_default_style,
+    } = column;
     let mut lines = Vec::new();
     let is_active = app.options_column == column;
 
@@ -153,7 +186,7 @@ fn item_style(palette: &ColorPalette, selected: bool, active: bool, default: Sty
 
 fn format_filter_display_name(app: &App, filter: &str) -> String {
     match app.group_by {
-        GroupBy::Model => filter.to_string(),
+        GroupBy::Model | GroupBy::Provider | GroupBy::ProviderModel => filter.to_string(),
         GroupBy::ApiKeys => {
             let api_key_names = &app.provider_info(app.current_provider()).api_key_names;
             api_key_names.get(filter).cloned().unwrap_or_else(|| {
@@ -174,12 +207,16 @@ fn render_group_by_options(
     is_active_column: bool,
     is_expanded: bool,
 ) {
-    let group_by_options = [GroupBy::Model, GroupBy::ApiKeys];
-    let is_usage_view = app.current_view == View::Usage;
+    let group_by_options: &[GroupBy] = if app.aggregate.is_some() {
+        &[GroupBy::Provider, GroupBy::ProviderModel]
+    } else {
+        &[GroupBy::Model, GroupBy::ApiKeys]
+    };
+    let available = app.group_by_options();
 
     for group_by in group_by_options.iter() {
         let is_selected = app.group_by == *group_by;
-        let is_disabled = !is_usage_view && *group_by == GroupBy::ApiKeys;
+        let is_disabled = !available.contains(group_by);
 
         let prefix = if is_expanded {
             if is_selected {
@@ -196,6 +233,8 @@ fn render_group_by_options(
         let label = match group_by {
             GroupBy::Model => "Model",
             GroupBy::ApiKeys => "API Keys",
+            GroupBy::Provider => "Provider",
+            GroupBy::ProviderModel => "Provider + Model",
         };
 
         let expansion_indicator = if is_expanded {
@@ -1,21 +1,25 @@
-use crate::app::App;
+use crate::app::{App, CustomRangeInput};
+use crate::provider::ProviderInfo;
 use crate::ui::colors::ColorPalette;
 use ratatui::{
     layout::{Alignment, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 
 pub fn render(f: &mut Frame, app: &App) {
     let area = f.size();
 
-    if let Some(popup_provider) = app.api_key_popup_active {
-        render_api_key_popup(f, area, popup_provider, &app.api_key_input);
-    } else if app.loading {
-        let provider = app.current_provider();
-        let palette = ColorPalette::for_provider(provider);
+    if let Some(input) = &app.custom_range_input {
+        let palette = app.scope_palette(app.scope());
+        render_custom_range_popup(f, area, input, &palette);
+    } else if let Some(popup_provider) = app.api_key_popup_active {
+        let account = app.provider_info(popup_provider);
+        render_api_key_popup(f, area, account, &app.api_key_input);
+    } else if app.scope_is_loading(app.scope()) && !app.scope_has_data(app.scope()) {
+        let palette = app.scope_palette(app.scope());
         render_loading_popup(f, area, palette);
     }
 }
@@ -26,7 +30,7 @@ fn create_centered_popup(area: Rect, width: u16, height: u16) -> Rect {
     Rect::new(x, y, width, height)
 }
 
-fn create_popup_block(title: &str, primary_color: Color) -> Block {
+fn create_popup_block(title: &str, primary_color: Color) -> Block<'_> {
     Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(primary_color))
@@ -45,35 +49,78 @@ fn render_loading_popup(f: &mut Frame, area: Rect, palette: ColorPalette) {
 
     f.render_widget(block, popup_area);
     f.render_widget(
-        Paragraph::new("Fetching usage data...")
-            .alignment(Alignment::Center)
-            .style(Style::default().fg(Color::White)),
+        Paragraph::new(vec![
+            Line::from("Fetching usage data..."),
+            Line::from(Span::styled(
+                "Esc to cancel",
+                Style::default().fg(Color::DarkGray),
+            )),
+        ])
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::White)),
         inner,
     );
 }
 
-fn render_api_key_popup(
+fn render_custom_range_popup(
     f: &mut Frame,
     area: Rect,
-    provider: crate::provider::Provider,
-    input_text: &str,
+    input: &CustomRangeInput,
+    palette: &ColorPalette,
 ) {
-    let palette = ColorPalette::for_provider(provider);
+    let popup_area = create_centered_popup(area, 50, 10);
+    let block = create_popup_block(" Custom Date Range ", palette.primary);
+    let inner = block.inner(popup_area);
+
+    let field_line = |label: &'static str, value: &str, active: bool| {
+        let cursor = if active { "_" } else { "" };
+        let value_style = if active {
+            Style::default()
+                .fg(palette.primary)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        Line::from(vec![
+            Span::styled(label, Style::default().fg(Color::White)),
+            Span::styled(format!("{}{}", value, cursor), value_style),
+        ])
+    };
+
+    let mut lines = vec![
+        Line::from(""),
+        field_line("Start (YYYY-MM-DD): ", &input.start, !input.editing_end),
+        field_line("End   (YYYY-MM-DD): ", &input.end, input.editing_end),
+        Line::from(""),
+    ];
+    if let Some(error) = &input.error {
+        lines.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(palette.error),
+        )));
+    } else {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Tab to switch field, Enter to apply, Esc to cancel",
+        Style::default().fg(palette.primary),
+    )));
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(block, popup_area);
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Left), inner);
+}
+
+fn render_api_key_popup(f: &mut Frame, area: Rect, account: &ProviderInfo, input_text: &str) {
+    let palette = account.provider.palette();
     let popup_area = create_centered_popup(area, 80, 10);
-    let title = format!(" Enter {} API Key ", provider.label());
+    let title = format!(" Enter {} API Key ", account.label());
     let block = create_popup_block(&title, palette.primary);
     let inner = block.inner(popup_area);
 
-    let (key_url, env_var_name) = match provider {
-        crate::provider::Provider::OpenAI => (
-            "https://platform.openai.com/settings/organization/admin-keys",
-            "OPENAI_ADMIN_KEY",
-        ),
-        crate::provider::Provider::Anthropic => (
-            "https://console.anthropic.com/settings/admin-keys",
-            "ANTHROPIC_ADMIN_KEY",
-        ),
-    };
+    let key_url = account.provider.admin_key_url();
+    let env_var_name = &account.env_var;
 
     f.render_widget(block, popup_area);
     f.render_widget(
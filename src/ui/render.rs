@@ -1,18 +1,26 @@
 use crate::app::App;
 use crate::ui::{content, footer, options, popup, summary};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     Frame,
 };
 
 pub fn render(f: &mut Frame, app: &mut App) {
+    let scope = app.scope();
+    // Data that is being replaced stays on screen, dimmed, while the footer
+    // gets a line for the refresh's progress. Budget warnings get one too.
+    let refreshing = app.scope_is_loading(scope);
+    let stale = refreshing && app.scope_has_data(scope);
+    let footer_height = 3 + refreshing as u16 + !app.budget_alerts().is_empty() as u16;
+
     // Top panel (options + summary) - use fixed height for better space utilization
     let vertical_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(12), // Fixed height for options/summary
-            Constraint::Min(10),    // Chart gets remaining space
-            Constraint::Length(3),  // Footer
+            Constraint::Length(12),            // Fixed height for options/summary
+            Constraint::Min(10),               // Chart gets remaining space
+            Constraint::Length(footer_height), // Footer
         ])
         .split(f.size());
 
@@ -25,18 +33,32 @@ pub fn render(f: &mut Frame, app: &mut App) {
         let app_ref = &*app;
         options::render(f, app_ref, top_chunks[0]);
         summary::render(f, app_ref, top_chunks[1]);
+        if stale {
+            dim(f, top_chunks[1]);
+        }
     }
 
     // Middle section: full width chart
     content::render(f, app, vertical_chunks[1]);
+    if stale {
+        dim(f, vertical_chunks[1]);
+    }
 
     // Bottom: footer
     {
         let app_ref = &*app;
         footer::render(f, app_ref, vertical_chunks[2]);
-        // Show popup overlay if loading or API key popup is active
-        if app_ref.loading || app_ref.api_key_popup_active.is_some() {
+        // Show popup overlay if loading without cached data or another popup is active
+        if refreshing
+            || app_ref.api_key_popup_active.is_some()
+            || app_ref.custom_range_input.is_some()
+        {
             popup::render(f, app_ref);
         }
     }
 }
+
+fn dim(f: &mut Frame, area: Rect) {
+    f.buffer_mut()
+        .set_style(area, Style::default().add_modifier(Modifier::DIM));
+}
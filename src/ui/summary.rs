@@ -1,10 +1,13 @@
-use crate::app::{App, GroupBy, Range, View};
-use crate::models::{DailyData, DailyUsageData};
+use crate::app::{App, BucketWindow, GroupBy, Scope, View};
+use crate::budget::BudgetStatus;
+use crate::models::{BucketWidth, DailyData, DailyUsageData};
 use crate::ui::banner;
 use crate::ui::colors::ColorPalette;
 use crate::ui::content::shared;
-use crate::ui::utils::format_tokens;
-use chrono::{DateTime, Duration, Utc};
+use crate::ui::utils::{
+    format_age, format_budget_amount, format_estimate, format_forecast, format_tokens,
+};
+use chrono::{DateTime, Utc};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -14,13 +17,10 @@ use ratatui::{
 };
 
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
-    let provider = app.current_provider();
-    let palette = ColorPalette::for_provider(provider);
-
-    let info = app.provider_info(provider);
-    let has_data = !info.cost_data.is_empty() || !info.usage_data.is_empty();
+    let scope = app.scope();
+    let palette = app.scope_palette(scope);
 
-    if app.loading || !has_data {
+    if !app.scope_has_data(scope) {
         let mut text = vec![];
         text.extend(banner::render_animated_banner(app, &palette));
         let block = Block::default()
@@ -39,11 +39,14 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
     } else {
         None
     };
-    let (total_cost, cost_bounds) =
-        summarize_cost(&info.cost_data, app.range, cost_filter, GroupBy::Model);
+    // Both metrics share the same window, so buckets without any data count
+    // as zero and averages cover the whole range.
+    let window = app.range_window();
+    let cost_data = app.cost_data(scope);
+    let total_cost = summarize_cost(&cost_data, window, cost_filter);
     let cost_period_comparison = compare_periods(
-        &info.cost_data,
-        app.range,
+        &cost_data,
+        window,
         |d| d.date,
         |d| d.cost,
         cost_filter,
@@ -55,41 +58,39 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
     } else {
         None
     };
-    let ((input_tokens, output_tokens), usage_bounds) =
-        summarize_usage(&info.usage_data, app.range, usage_filter, app.group_by);
-    let cache_hit_rate =
-        calculate_cache_hit_rate(&info.usage_data, app.range, usage_filter, app.group_by);
-    let total_requests =
-        calculate_total_requests(&info.usage_data, app.range, usage_filter, app.group_by);
+    let usage_data = &*app.usage_data(scope);
+    let (input_tokens, output_tokens) =
+        summarize_usage(usage_data, window, usage_filter, app.group_by);
+    let cache_hit_rate = calculate_cache_hit_rate(usage_data, window, usage_filter, app.group_by);
+    let total_requests = calculate_total_requests(usage_data, window, usage_filter, app.group_by);
     let token_period_comparison = compare_periods(
-        &info.usage_data,
-        app.range,
+        usage_data,
+        window,
         |d| d.date,
         |d| (d.input_tokens + d.output_tokens) as f64,
         usage_filter,
         |d| match app.group_by {
-            GroupBy::Model => shared::extract_trimmed_string(&d.model),
             GroupBy::ApiKeys => shared::extract_trimmed_string(&d.api_key_id),
+            GroupBy::Model | GroupBy::Provider | GroupBy::ProviderModel => {
+                shared::extract_trimmed_string(&d.model)
+            }
         },
     );
 
-    let range_days = app.range.days().max(1) as f64;
-    let avg_cost_per_day = total_cost / range_days;
+    let buckets = window.len as f64;
+    let avg_cost_per_day = total_cost / buckets;
     let total_tokens = input_tokens + output_tokens;
-    let avg_tokens_per_day = total_tokens as f64 / range_days;
-
-    let date_range = {
-        cost_bounds
-            .or(usage_bounds)
-            .map(|(min_date, max_date)| {
-                format!(
-                    "{} - {}",
-                    min_date.format("%m/%d"),
-                    max_date.format("%m/%d")
-                )
-            })
-            .unwrap_or_else(|| "No data in selected range".to_string())
-    };
+    let avg_tokens_per_day = total_tokens as f64 / buckets;
+    let average_label = format!("Average per {}: ", window.width.unit());
+    let hourly = window.width == BucketWidth::Hour;
+
+    let date_range = format!(
+        "{} - {} ({} {}s)",
+        window.label(window.start),
+        window.label(window.end() - window.width.duration()),
+        window.len,
+        window.width.unit()
+    );
 
     let block = Block::default()
         .borders(Borders::ALL)
@@ -102,9 +103,13 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(block, area);
 
     // Vertical layout: main content area + date range footer
+    // Truncated reports get a line of their own under the date range, since
+    // they mean the totals above are too low.
+    let warnings = app.scope_warnings(scope);
+    let footer_height = if warnings.is_empty() { 1 } else { 2 };
     let main_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .constraints([Constraint::Min(0), Constraint::Length(footer_height)])
         .split(inner);
 
     // Horizontal layout: Cost column (left) + Usage column (right)
@@ -127,34 +132,58 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
             .add_modifier(Modifier::BOLD),
     )));
     cost_text.push(Line::from(""));
-    add_labeled_value(
-        &mut cost_text,
-        format!("Total ({}): ", app.range.label()),
-        format!("${:.2}", total_cost),
-        &palette,
-    );
-    add_labeled_value(
-        &mut cost_text,
-        "Average per day: ",
-        format!("${:.2}", avg_cost_per_day),
-        &palette,
-    );
+    if hourly {
+        // Neither cost API reports anything finer than a day.
+        cost_text.push(Line::from(Span::styled(
+            "Cost is only reported per day",
+            Style::default().fg(Color::DarkGray),
+        )));
+    } else {
+        add_labeled_value(
+            &mut cost_text,
+            format!("Total ({}): ", app.range.label()),
+            format!("${:.2}", total_cost),
+            &palette,
+        );
+        add_labeled_value(
+            &mut cost_text,
+            average_label.clone(),
+            format!("${:.2}", avg_cost_per_day),
+            &palette,
+        );
+        if let Some(forecast) = app.cost_forecast(scope, cost_filter.map(String::as_str)) {
+            add_labeled_value(
+                &mut cost_text,
+                "Month-end forecast: ",
+                format_forecast(&forecast),
+                &palette,
+            );
+        }
+    }
     if app.range == crate::app::Range::SevenDays {
         add_period_comparison(&mut cost_text, cost_period_comparison);
     }
+    // Budgets follow the calendar rather than the selected range.
+    let budgets = app.budget_statuses(Some(scope));
+    if !budgets.is_empty() {
+        cost_text.push(Line::from(""));
+        for status in &budgets {
+            add_budget(&mut cost_text, status, &palette);
+        }
+    }
 
     // Build Usage column content
     let mut usage_text = vec![];
     let usage_header = if let Some(filter) = usage_filter {
-        let display_name = match app.group_by {
-            GroupBy::Model => filter.clone(),
-            GroupBy::ApiKeys => {
-                let api_key_names = &info.api_key_names;
+        let display_name = match (app.group_by, scope) {
+            (GroupBy::ApiKeys, Scope::Provider(provider)) => {
+                let api_key_names = &app.provider_info(provider).api_key_names;
                 api_key_names
                     .get(filter)
                     .cloned()
                     .unwrap_or_else(|| shared::abbreviate_api_key(filter))
             }
+            _ => filter.clone(),
         };
         format!("Usage: {}", display_name)
     } else {
@@ -175,7 +204,7 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
     );
     add_labeled_value(
         &mut usage_text,
-        "Average per day: ",
+        average_label.clone(),
         format_tokens(avg_tokens_per_day as u64),
         &palette,
     );
@@ -196,6 +225,17 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
                 .add_modifier(Modifier::BOLD),
         ),
     ]));
+    let (estimated_cost, partial) = app
+        .estimated_costs(scope)
+        .total(usage_filter.map(String::as_str));
+    if estimated_cost > 0.0 || partial {
+        add_labeled_value(
+            &mut usage_text,
+            "Estimated cost: ",
+            format_estimate(estimated_cost, partial),
+            &palette,
+        );
+    }
     if app.range == crate::app::Range::SevenDays {
         add_period_comparison(&mut usage_text, token_period_comparison);
     }
@@ -207,16 +247,15 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
             format!("{}", requests),
             &palette,
         );
-        let avg_requests_per_day = requests as f64 / range_days;
+        let avg_requests_per_day = requests as f64 / buckets;
         add_labeled_value(
             &mut usage_text,
-            "Average per day: ",
+            average_label,
             format!("{:.0}", avg_requests_per_day),
             &palette,
         );
     }
     if let Some(hit_rate) = cache_hit_rate {
-        usage_text.push(Line::from(""));
         add_labeled_value(
             &mut usage_text,
             "Cache hit rate: ",
@@ -226,10 +265,34 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
     }
 
     // Date range footer
-    let date_range_text = vec![Line::from(vec![
+    let mut date_range_spans = vec![
         Span::styled("Date Range: ", Style::default().fg(palette.primary)),
         Span::raw(date_range),
-    ])];
+    ];
+    if let Some(updated) = app.last_updated(scope) {
+        let age = format_age(Utc::now() - updated);
+        let status = if app.scope_is_loading(scope) {
+            format!("  Updated {} (refreshing...)", age)
+        } else {
+            format!("  Updated {}", age)
+        };
+        date_range_spans.push(Span::styled(status, Style::default().fg(Color::DarkGray)));
+    }
+    let retries = app.scope_retries(scope);
+    if retries > 0 {
+        let noun = if retries == 1 { "request" } else { "requests" };
+        date_range_spans.push(Span::styled(
+            format!("  {} {} retried", retries, noun),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    let mut date_range_text = vec![Line::from(date_range_spans)];
+    if !warnings.is_empty() {
+        date_range_text.push(Line::from(Span::styled(
+            format!("Totals may be low: {}", warnings.join("; ")),
+            Style::default().fg(palette.error),
+        )));
+    }
 
     // Render columns and footer
     f.render_widget(Paragraph::new(cost_text), columns[0]);
@@ -254,6 +317,27 @@ fn add_labeled_value(
     ]));
 }
 
+fn add_budget(text: &mut Vec<Line>, status: &BudgetStatus, palette: &ColorPalette) {
+    let (label, color) = if status.breached() {
+        (format!("⚠ {}: ", status.budget.label()), palette.error)
+    } else if status.warning() {
+        (format!("{}: ", status.budget.label()), Color::Yellow)
+    } else {
+        (format!("{}: ", status.budget.label()), palette.primary)
+    };
+    text.push(Line::from(vec![
+        Span::styled(label, Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!(
+                "{} ({:.0}%)",
+                format_budget_amount(status),
+                status.percent()
+            ),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+    ]));
+}
+
 fn add_period_comparison(text: &mut Vec<Line>, comparison: Option<(f64, String)>) {
     if let Some((change_pct, direction)) = comparison {
         let change_color = if change_pct >= 0.0 {
@@ -273,109 +357,69 @@ fn add_period_comparison(text: &mut Vec<Line>, comparison: Option<(f64, String)>
     }
 }
 
-fn range_cutoff(range: Range, latest: DateTime<Utc>) -> DateTime<Utc> {
-    let span = range.days().saturating_sub(1);
-    latest - Duration::days(span)
-}
-
-fn summarize_cost(
+pub fn summarize_cost(
     data: &[DailyData],
-    range: Range,
+    window: BucketWindow,
     selected_filter: Option<&String>,
-    group_by: GroupBy,
-) -> (f64, Option<(DateTime<Utc>, DateTime<Utc>)>) {
-    if data.is_empty() {
-        return (0.0, None);
-    }
-
-    let latest = match data.iter().map(|d| d.date).max() {
-        Some(date) => date,
-        None => return (0.0, None),
-    };
-    let cutoff = range_cutoff(range, latest);
-    let mut filtered: Vec<_> = data.iter().filter(|d| d.date >= cutoff).collect();
+) -> f64 {
+    let mut filtered: Vec<_> = data.iter().filter(|d| window.contains(d.date)).collect();
 
     if let Some(filter) = selected_filter {
-        if group_by == GroupBy::Model {
-            filtered = filtered
-                .into_iter()
-                .filter(|d| {
-                    shared::extract_trimmed_string(&d.line_item)
-                        .map(|s| s == filter.as_str())
-                        .unwrap_or(false)
-                })
-                .collect();
-        }
+        filtered.retain(|d| {
+            shared::extract_trimmed_string(&d.line_item)
+                .map(|s| s == filter.as_str())
+                .unwrap_or(false)
+        });
     }
 
-    if filtered.is_empty() {
-        return (0.0, None);
-    }
-
-    let total: f64 = filtered.iter().map(|d| d.cost).sum();
-    let min_date = filtered.iter().map(|d| d.date).min().unwrap();
-    let max_date = filtered.iter().map(|d| d.date).max().unwrap();
-
-    (total, Some((min_date, max_date)))
+    filtered.iter().map(|d| d.cost).sum()
 }
 
 fn filter_usage_data_by_range_and_filter<'a>(
     data: &'a [DailyUsageData],
-    range: Range,
+    window: BucketWindow,
     selected_filter: Option<&String>,
     group_by: GroupBy,
 ) -> Vec<&'a DailyUsageData> {
-    let latest = match data.iter().map(|d| d.date).max() {
-        Some(date) => date,
-        None => return Vec::new(),
-    };
-    let cutoff = range_cutoff(range, latest);
-    let mut filtered: Vec<_> = data.iter().filter(|d| d.date >= cutoff).collect();
+    let mut filtered: Vec<_> = data.iter().filter(|d| window.contains(d.date)).collect();
 
     if let Some(filter) = selected_filter {
-        filtered = filtered
-            .into_iter()
-            .filter(|d| {
-                let field_value = match group_by {
-                    GroupBy::Model => shared::extract_trimmed_string(&d.model),
-                    GroupBy::ApiKeys => shared::extract_trimmed_string(&d.api_key_id),
-                };
-                field_value.map(|s| s == filter.as_str()).unwrap_or(false)
-            })
-            .collect();
+        filtered.retain(|d| {
+            let field_value = match group_by {
+                GroupBy::ApiKeys => shared::extract_trimmed_string(&d.api_key_id),
+                GroupBy::Model | GroupBy::Provider | GroupBy::ProviderModel => {
+                    shared::extract_trimmed_string(&d.model)
+                }
+            };
+            field_value.map(|s| s == filter.as_str()).unwrap_or(false)
+        });
     }
 
     filtered
 }
 
-fn summarize_usage(
+pub fn summarize_usage(
     data: &[DailyUsageData],
-    range: Range,
+    window: BucketWindow,
     selected_filter: Option<&String>,
     group_by: GroupBy,
-) -> ((u64, u64), Option<(DateTime<Utc>, DateTime<Utc>)>) {
-    let filtered = filter_usage_data_by_range_and_filter(data, range, selected_filter, group_by);
-
-    if filtered.is_empty() {
-        return ((0, 0), None);
-    }
+) -> (u64, u64) {
+    let filtered = filter_usage_data_by_range_and_filter(data, window, selected_filter, group_by);
 
     let input_total: u64 = filtered.iter().map(|d| d.input_tokens).sum();
     let output_total: u64 = filtered.iter().map(|d| d.output_tokens).sum();
-    let min_date = filtered.iter().map(|d| d.date).min().unwrap();
-    let max_date = filtered.iter().map(|d| d.date).max().unwrap();
 
-    ((input_total, output_total), Some((min_date, max_date)))
+    (input_total, output_total)
 }
 
 fn calculate_cache_hit_rate(
     usage_data: &[DailyUsageData],
-    range: Range,
+    window: BucketWindow,
     selected_filter: Option<&String>,
     group_by: GroupBy,
 ) -> Option<f64> {
     let filtered =
-        filter_usage_data_by_range_and_filter(usage_data, range, selected_filter, group_by);
+        filter_usage_data_by_range_and_filter(usage_data, window, selected_filter, group_by);
 
     let (cache_read_total, uncached_total): (u64, u64) = filtered
         .iter()
@@ -392,12 +436,12 @@ fn calculate_cache_hit_rate(
 
 fn calculate_total_requests(
     usage_data: &[DailyUsageData],
-    range: Range,
+    window: BucketWindow,
     selected_filter: Option<&String>,
     group_by: GroupBy,
 ) -> Option<u64> {
     let filtered =
-        filter_usage_data_by_range_and_filter(usage_data, range, selected_filter, group_by);
+        filter_usage_data_by_range_and_filter(usage_data, window, selected_filter, group_by);
     let total: u64 = filtered.iter().filter_map(|d| d.num_requests).sum();
 
     if total > 0 {
@@ -407,26 +451,19 @@ fn calculate_total_requests(
     }
 }
 
-fn compare_periods<T>(
+pub fn compare_periods<T>(
     data: &[T],
-    range: Range,
+    window: BucketWindow,
     extract_date: impl Fn(&T) -> DateTime<Utc>,
     extract_value: impl Fn(&T) -> f64,
     selected_filter: Option<&String>,
     extract_filter_field: impl Fn(&T) -> Option<&str>,
 ) -> Option<(f64, String)> {
-    if data.is_empty() {
-        return None;
-    }
-
-    let latest = data.iter().map(&extract_date).max()?;
-    let cutoff = range_cutoff(range, latest);
-    let period_days = range.days() as i64;
-    let previous_cutoff = cutoff - Duration::days(period_days);
+    let previous_window = window.previous();
 
     let current: f64 = data
         .iter()
-        .filter(|d| extract_date(d) >= cutoff)
+        .filter(|d| window.contains(extract_date(d)))
         .filter(|d| {
             if let Some(filter) = selected_filter {
                 extract_filter_field(d)
@@ -440,10 +477,7 @@ fn compare_periods<T>(
         .sum();
     let previous: f64 = data
         .iter()
-        .filter(|d| {
-            let date = extract_date(d);
-            date >= previous_cutoff && date < cutoff
-        })
+        .filter(|d| previous_window.contains(extract_date(d)))
         .filter(|d| {
             if let Some(filter) = selected_filter {
                 extract_filter_field(d)
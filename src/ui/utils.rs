@@ -1,3 +1,63 @@
+use crate::budget::BudgetStatus;
+use crate::forecast::Forecast;
+
+pub fn format_age(age: chrono::Duration) -> String {
+    let minutes = age.num_minutes();
+    if minutes < 1 {
+        "just now".to_string()
+    } else if minutes < 60 {
+        format!("{} min ago", minutes)
+    } else if minutes < 60 * 24 {
+        format!("{}h ago", minutes / 60)
+    } else {
+        format!("{}d ago", minutes / (60 * 24))
+    }
+}
+
+pub fn format_countdown(remaining: chrono::Duration) -> String {
+    let secs = remaining.num_seconds().max(0);
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// A budget's spend against its limit, e.g. "$412.50 / $500.00".
+pub fn format_budget_amount(status: &BudgetStatus) -> String {
+    match status.budget.tokens {
+        Some(tokens) => format!(
+            "{} / {} tokens",
+            format_tokens(status.spent as u64),
+            format_tokens(tokens)
+        ),
+        None => format!("${:.2} / ${:.2}", status.spent, status.budget.limit()),
+    }
+}
+
+/// Projected month-end spend and its band, e.g. "~$1240 ($1180-$1310)".
+pub fn format_forecast(forecast: &Forecast) -> String {
+    let dollars = |amount: f64| {
+        if amount >= 100.0 {
+            format!("${:.0}", amount)
+        } else {
+            format!("${:.2}", amount)
+        }
+    };
+    format!(
+        "~{} ({}-{})",
+        dollars(forecast.projected),
+        dollars(forecast.low),
+        dollars(forecast.high)
+    )
+}
+
+/// A token-derived cost, e.g. "~$12.40", with "+" when some tokens had no
+/// price and aren't counted.
+pub fn format_estimate(cost: f64, partial: bool) -> String {
+    format!("~${:.2}{}", cost, if partial { "+" } else { "" })
+}
+
 pub fn format_tokens(tokens: u64) -> String {
     if tokens >= 1_000_000 {
         format!("{:.1}M", tokens as f64 / 1_000_000.0)